@@ -0,0 +1,95 @@
+//! Check mode: detect commit messages that are not already rule72-clean.
+//!
+//! Instead of rewriting, the message is reflowed and compared against the
+//! original. Any difference is reported as a unified diff so that Git hooks
+//! and CI gates share the same engine as the formatter itself.
+
+use similar::TextDiff;
+
+use crate::reflow;
+use crate::types::Options;
+
+/// Reflow `input` and compare it against the original.
+///
+/// Returns `None` when the message is already formatted, otherwise a unified
+/// diff from the original to the reflowed text. A missing final newline is
+/// not considered a formatting difference.
+pub fn check(input: &str, opts: &Options) -> Option<String> {
+    check_named(input, opts, "original", "reflowed")
+}
+
+/// Like [`check`], but with custom diff header names (e.g. a file path).
+pub fn check_named(input: &str, opts: &Options, old_name: &str, new_name: &str) -> Option<String> {
+    let output = reflow(input, opts);
+    if output == input || output.strip_suffix('\n') == Some(input) {
+        return None;
+    }
+    Some(unified_diff(input, &output, old_name, new_name))
+}
+
+/// Reflow `input` twice and compare the two passes.
+///
+/// Returns `None` when formatting is stable, i.e. reflowing the reflowed
+/// message changes nothing, otherwise a unified diff from the first pass to
+/// the second. A diff here is a bug in rule72.
+pub fn check_idempotent(input: &str, opts: &Options) -> Option<String> {
+    check_named(
+        &reflow(input, opts),
+        opts,
+        "reflowed once",
+        "reflowed twice",
+    )
+}
+
+/// Render a unified diff between two texts with the given header names.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(old_name, new_name)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_clean_message() {
+        let input = "Subject line\n\nShort body paragraph.\n";
+        assert_eq!(check(input, &Options::default()), None);
+    }
+
+    #[test]
+    fn test_check_ignores_missing_final_newline() {
+        let input = "Subject line\n\nShort body paragraph.";
+        assert_eq!(check(input, &Options::default()), None);
+    }
+
+    #[test]
+    fn test_check_reports_diff() {
+        let input = "Subject line\n\nThis body line is much longer than the configured width and will be wrapped.\n";
+        let opts = Options {
+            width: 40,
+            ..Options::default()
+        };
+
+        let diff = check(input, &opts).expect("message should need reflow");
+        assert!(diff.starts_with("--- original\n+++ reflowed\n"));
+        assert!(diff.contains("-This body line is much longer"));
+        assert!(diff.contains("+This body line is much longer than the"));
+    }
+
+    #[test]
+    fn test_check_idempotent() {
+        // Wrapping must not move the footer tag or the marker to a line start
+        let input = "Subject line\n\nSee the report at the usual place. Fixes: the scheduler\n\n- an item whose text mentions - dashes and 2. numbers here\n";
+        for width in [20, 30, 40] {
+            let opts = Options {
+                width,
+                ..Options::default()
+            };
+            assert_eq!(check_idempotent(input, &opts), None, "width {width}");
+        }
+    }
+}
@@ -30,37 +30,32 @@ pub fn classify_with_context(mut cat_lines: Vec<CatLine>) -> Vec<CatLine> {
 
             // Context-based adjustments
             match neighbor.final_category {
-                Category::List => {
-                    // Lines near lists are more likely to be lists or prose
-                    if cat_lines[i].indent > 0 && cat_lines[i].final_category != Category::Code {
-                        *new_probabilities.entry(Category::List).or_insert(0.0) += 0.1;
-                        *new_probabilities
-                            .entry(Category::ProseGeneral)
-                            .or_insert(0.0) += 0.05;
-                    }
+                // Lines near lists are more likely to be lists or prose
+                Category::List
+                    if cat_lines[i].indent > 0 && cat_lines[i].final_category != Category::Code =>
+                {
+                    *new_probabilities.entry(Category::List).or_insert(0.0) += 0.1;
+                    *new_probabilities
+                        .entry(Category::ProseGeneral)
+                        .or_insert(0.0) += 0.05;
                 }
-                Category::Code => {
-                    // Lines near code blocks with similar indentation are likely code
+                // Lines near code blocks with similar indentation are likely code
+                Category::Code
                     if cat_lines[i].indent >= 4
-                        && cat_lines[i].indent.abs_diff(neighbor.indent) <= 2
-                    {
-                        *new_probabilities.entry(Category::Code).or_insert(0.0) += 0.15;
-                    }
+                        && cat_lines[i].indent.abs_diff(neighbor.indent) <= 2 =>
+                {
+                    *new_probabilities.entry(Category::Code).or_insert(0.0) += 0.15;
                 }
-                Category::Table => {
-                    // Lines near tables that look table-like get boosted
-                    if cat_lines[i].text.contains('|') {
-                        *new_probabilities.entry(Category::Table).or_insert(0.0) += 0.2;
-                    }
+                // Lines near tables that look table-like get boosted
+                Category::Table if cat_lines[i].text.contains('|') => {
+                    *new_probabilities.entry(Category::Table).or_insert(0.0) += 0.2;
                 }
-                Category::ProseIntroduction => {
-                    // After introduction, next lines are often lists or prose
-                    if offset == 1 {
-                        *new_probabilities.entry(Category::List).or_insert(0.0) += 0.1;
-                        *new_probabilities
-                            .entry(Category::ProseGeneral)
-                            .or_insert(0.0) += 0.1;
-                    }
+                // After introduction, next lines are often lists or prose
+                Category::ProseIntroduction if offset == 1 => {
+                    *new_probabilities.entry(Category::List).or_insert(0.0) += 0.1;
+                    *new_probabilities
+                        .entry(Category::ProseGeneral)
+                        .or_insert(0.0) += 0.1;
                 }
                 _ => {}
             }
@@ -84,7 +79,7 @@ pub fn classify_with_context(mut cat_lines: Vec<CatLine>) -> Vec<CatLine> {
         // Update final category based on new probabilities
         let final_category = new_probabilities
             .iter()
-            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .max_by(|a, b| a.1.total_cmp(b.1).then_with(|| b.0.cmp(a.0)))
             .map(|(cat, _)| *cat)
             .unwrap_or(Category::ProseGeneral);
 
@@ -117,6 +112,7 @@ mod tests {
             headline_width: 50,
             debug_svg: None,
             debug_trace: false,
+            ..Options::default()
         };
         let lexed = lex_lines(&lines, &opts);
         let classified = classify_with_context(lexed);
@@ -0,0 +1,434 @@
+//! Repository configuration from `.rule72.toml` files and `rule72.*` git config keys.
+//!
+//! Settings are merged in increasing order of precedence:
+//! built-in defaults < `.rule72.toml` < `rule72.*` git config < CLI flags.
+//! The file is searched for from the current directory up to the repository
+//! root, so a single committed file applies to every developer and hook.
+//!
+//! ```toml
+//! width = 72
+//! headline-width = 50
+//! wrap = "optimal"
+//! reflow = "minimal"
+//! renumber-lists = true
+//! normalize-bullets = true
+//! comment-char = ";"
+//! footer-tags = ["Change-Id", "Ticket"]
+//! footer-deny = ["EN"]
+//! trailer-separators = ":#"
+//! conventional = true
+//! conventional-types = ["feat", "fix", "chore"]
+//! conventional-scopes = ["api", "cli"]
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+use crate::git;
+use crate::types::{Options, ReflowMode, WrapAlgorithm};
+
+/// Name of the repository configuration file
+pub const CONFIG_FILE: &str = ".rule72.toml";
+
+/// Partial formatting options; unset fields leave [`Options`] untouched
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub width: Option<usize>,
+    pub headline_width: Option<usize>,
+    pub wrap: Option<WrapAlgorithm>,
+    pub reflow: Option<ReflowMode>,
+    pub renumber_lists: Option<bool>,
+    pub normalize_bullets: Option<bool>,
+    pub comment_char: Option<char>,
+    pub footer_tags: Option<Vec<String>>,
+    pub footer_deny: Option<Vec<String>>,
+    pub trailer_separators: Option<String>,
+    pub conventional: Option<bool>,
+    pub conventional_types: Option<Vec<String>>,
+    pub conventional_scopes: Option<Vec<String>>,
+}
+
+impl Config {
+    /// Load the `.rule72.toml` nearest to `start` and merge `rule72.*` git config over it
+    pub fn discover(start: &Path) -> Result<Config> {
+        let file_config = match find_config_file(start) {
+            Some(path) => Config::from_file(&path)?,
+            None => Config::default(),
+        };
+        Ok(file_config.merge(Config::from_git_config()?))
+    }
+
+    /// Parse a `.rule72.toml` file
+    pub fn from_file(path: &Path) -> Result<Config> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Config::from_toml(&text).with_context(|| format!("invalid {}", path.display()))
+    }
+
+    /// Parse configuration from TOML text
+    pub fn from_toml(text: &str) -> Result<Config> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// Read `rule72.*` keys from git config (e.g. `rule72.width`,
+    /// `rule72.headlineWidth`, `rule72.renumberLists`, `rule72.commentChar`,
+    /// `rule72.footerTags`, `rule72.conventional`, `rule72.conventionalTypes`).
+    /// Git's own `trailer.separators` is used unless `rule72.trailerSeparators` is set.
+    /// Unknown keys only print a warning, so that a typo in the global git
+    /// config does not stop every commit hook.
+    pub fn from_git_config() -> Result<Config> {
+        Config::from_git_entries(&git::config_get_regexp(
+            r"^(rule72\..*|trailer\.separators)$",
+        ))
+        .context("invalid git config")
+    }
+
+    /// Build configuration from `(key, value)` pairs as printed by
+    /// `git config --get-regexp`; keys are lowercase as git normalises them.
+    /// Repeated list entries such as `rule72.footerTags` accumulate.
+    pub fn from_git_entries(entries: &[(String, String)]) -> Result<Config> {
+        let mut config = Config::default();
+        let mut git_separators = None;
+        for (key, value) in entries {
+            if key == "trailer.separators" {
+                git_separators = Some(value.clone());
+                continue;
+            }
+
+            let name = key.strip_prefix("rule72.").unwrap_or(key);
+            match name {
+                "width" => config.width = Some(parse_number(key, value)?),
+                "headlinewidth" => config.headline_width = Some(parse_number(key, value)?),
+                "wrap" => config.wrap = Some(value.parse().map_err(|err| anyhow!("{key}: {err}"))?),
+                "reflow" => {
+                    config.reflow = Some(value.parse().map_err(|err| anyhow!("{key}: {err}"))?)
+                }
+                "renumberlists" => config.renumber_lists = Some(parse_bool(key, value)?),
+                "normalizebullets" => config.normalize_bullets = Some(parse_bool(key, value)?),
+                "commentchar" => {
+                    let mut chars = value.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => config.comment_char = Some(c),
+                        _ => bail!("{key} must be a single character, got {value:?}"),
+                    }
+                }
+                "footertags" => config
+                    .footer_tags
+                    .get_or_insert_with(Vec::new)
+                    .extend(split_list(value)),
+                "footerdeny" => config
+                    .footer_deny
+                    .get_or_insert_with(Vec::new)
+                    .extend(split_list(value)),
+                "trailerseparators" => config.trailer_separators = Some(value.clone()),
+                "conventional" => config.conventional = Some(parse_bool(key, value)?),
+                "conventionaltypes" => config
+                    .conventional_types
+                    .get_or_insert_with(Vec::new)
+                    .extend(split_list(value)),
+                "conventionalscopes" => config
+                    .conventional_scopes
+                    .get_or_insert_with(Vec::new)
+                    .extend(split_list(value)),
+                _ => eprintln!("rule72: warning: ignoring unknown git config key {key}"),
+            }
+        }
+        config.trailer_separators = config.trailer_separators.or(git_separators);
+        Ok(config)
+    }
+
+    /// Overlay `other` on top of `self`; fields set in `other` win
+    pub fn merge(self, other: Config) -> Config {
+        Config {
+            width: other.width.or(self.width),
+            headline_width: other.headline_width.or(self.headline_width),
+            wrap: other.wrap.or(self.wrap),
+            reflow: other.reflow.or(self.reflow),
+            renumber_lists: other.renumber_lists.or(self.renumber_lists),
+            normalize_bullets: other.normalize_bullets.or(self.normalize_bullets),
+            comment_char: other.comment_char.or(self.comment_char),
+            footer_tags: other.footer_tags.or(self.footer_tags),
+            footer_deny: other.footer_deny.or(self.footer_deny),
+            trailer_separators: other.trailer_separators.or(self.trailer_separators),
+            conventional: other.conventional.or(self.conventional),
+            conventional_types: other.conventional_types.or(self.conventional_types),
+            conventional_scopes: other.conventional_scopes.or(self.conventional_scopes),
+        }
+    }
+
+    /// Apply the fields that are set to `opts`
+    pub fn apply(&self, opts: &mut Options) {
+        if let Some(width) = self.width {
+            opts.width = width;
+        }
+        if let Some(headline_width) = self.headline_width {
+            opts.headline_width = headline_width;
+        }
+        if let Some(wrap) = self.wrap {
+            opts.wrap = wrap;
+        }
+        if let Some(reflow) = self.reflow {
+            opts.reflow = reflow;
+        }
+        if let Some(renumber_lists) = self.renumber_lists {
+            opts.renumber_lists = renumber_lists;
+        }
+        if let Some(normalize_bullets) = self.normalize_bullets {
+            opts.normalize_bullets = normalize_bullets;
+        }
+        if let Some(comment_char) = self.comment_char {
+            opts.comment_char = comment_char;
+        }
+        if let Some(footer_tags) = &self.footer_tags {
+            opts.footer_tags = footer_tags.clone();
+        }
+        if let Some(footer_deny) = &self.footer_deny {
+            opts.footer_deny = footer_deny.clone();
+        }
+        if let Some(trailer_separators) = &self.trailer_separators {
+            opts.trailer_separators = trailer_separators.clone();
+        }
+        if let Some(conventional) = self.conventional {
+            opts.conventional = conventional;
+        }
+        if let Some(types) = &self.conventional_types {
+            opts.conventional_types = types.clone();
+        }
+        if let Some(scopes) = &self.conventional_scopes {
+            opts.conventional_scopes = scopes.clone();
+        }
+    }
+}
+
+/// Walk up from `start` looking for `.rule72.toml`, stopping at the
+/// repository root (the directory containing `.git`).
+pub fn find_config_file(start: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        let candidate = dir.join(CONFIG_FILE);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
+/// Split a comma- or whitespace-separated git config list value
+fn split_list(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+}
+
+fn parse_number(key: &str, value: &str) -> Result<usize> {
+    value
+        .parse()
+        .with_context(|| format!("{key} must be a number, got {value:?}"))
+}
+
+/// Parse a git config boolean (`true`/`yes`/`on`/`1` and their negations)
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" | "" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => bail!("{key} must be a boolean, got {value:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let config = Config::from_toml(
+            "width = 80\nheadline-width = 60\nwrap = \"optimal\"\ncomment-char = \";\"\nfooter-tags = [\"Change-Id\"]\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.width, Some(80));
+        assert_eq!(config.headline_width, Some(60));
+        assert_eq!(config.wrap, Some(WrapAlgorithm::Optimal));
+        assert_eq!(config.comment_char, Some(';'));
+        assert_eq!(config.footer_tags, Some(vec!["Change-Id".to_string()]));
+    }
+
+    #[test]
+    fn test_from_toml_rejects_unknown_keys() {
+        assert!(Config::from_toml("widht = 80\n").is_err());
+        assert!(Config::from_toml("width = \"wide\"\n").is_err());
+        assert!(Config::from_toml("wrap = \"balanced\"\n").is_err());
+    }
+
+    #[test]
+    fn test_from_git_entries() {
+        let config = Config::from_git_entries(&[
+            entry("rule72.width", "100"),
+            entry("rule72.headlinewidth", "65"),
+            entry("rule72.wrap", "optimal"),
+            entry("rule72.commentchar", ";"),
+            entry("rule72.footertags", "Change-Id, Bug"),
+            entry("rule72.footertags", "Ticket"),
+        ])
+        .unwrap();
+
+        assert_eq!(config.width, Some(100));
+        assert_eq!(config.headline_width, Some(65));
+        assert_eq!(config.wrap, Some(WrapAlgorithm::Optimal));
+        assert_eq!(config.comment_char, Some(';'));
+        assert_eq!(
+            config.footer_tags,
+            Some(vec![
+                "Change-Id".to_string(),
+                "Bug".to_string(),
+                "Ticket".to_string()
+            ])
+        );
+
+        assert!(Config::from_git_entries(&[entry("rule72.width", "wide")]).is_err());
+        assert!(Config::from_git_entries(&[entry("rule72.wrap", "fast")]).is_err());
+        assert!(Config::from_git_entries(&[entry("rule72.commentchar", "//")]).is_err());
+        // Unknown keys are skipped
+        let config =
+            Config::from_git_entries(&[entry("rule72.colour", "red"), entry("rule72.width", "80")])
+                .unwrap();
+        assert_eq!(config.width, Some(80));
+    }
+
+    #[test]
+    fn test_from_git_entries_trailers() {
+        let config = Config::from_git_entries(&[
+            entry("trailer.separators", ":#"),
+            entry("rule72.footerdeny", "EN,DE"),
+        ])
+        .unwrap();
+        assert_eq!(config.trailer_separators.as_deref(), Some(":#"));
+        assert_eq!(
+            config.footer_deny,
+            Some(vec!["EN".to_string(), "DE".to_string()])
+        );
+
+        // rule72.trailerSeparators wins over git's own setting regardless of order
+        let config = Config::from_git_entries(&[
+            entry("rule72.trailerseparators", ":"),
+            entry("trailer.separators", ":#"),
+        ])
+        .unwrap();
+        assert_eq!(config.trailer_separators.as_deref(), Some(":"));
+    }
+
+    #[test]
+    fn test_conventional_settings() {
+        let config = Config::from_toml(
+            "conventional = true\nconventional-types = [\"feat\", \"fix\"]\nconventional-scopes = [\"api\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.conventional, Some(true));
+        assert_eq!(
+            config.conventional_types,
+            Some(vec!["feat".to_string(), "fix".to_string()])
+        );
+
+        let config = Config::from_git_entries(&[
+            entry("rule72.conventional", "yes"),
+            entry("rule72.conventionaltypes", "feat fix"),
+            entry("rule72.conventionalscopes", "api,cli"),
+        ])
+        .unwrap();
+        let mut opts = Options::default();
+        config.apply(&mut opts);
+        assert!(opts.conventional);
+        assert_eq!(opts.conventional_types, vec!["feat", "fix"]);
+        assert_eq!(opts.conventional_scopes, vec!["api", "cli"]);
+
+        assert!(Config::from_git_entries(&[entry("rule72.conventional", "maybe")]).is_err());
+    }
+
+    #[test]
+    fn test_list_settings() {
+        let config =
+            Config::from_toml("renumber-lists = true\nnormalize-bullets = false\n").unwrap();
+        assert_eq!(config.renumber_lists, Some(true));
+        assert_eq!(config.normalize_bullets, Some(false));
+
+        let config = Config::from_git_entries(&[
+            entry("rule72.renumberlists", "on"),
+            entry("rule72.normalizebullets", "true"),
+        ])
+        .unwrap();
+        let mut opts = Options::default();
+        config.apply(&mut opts);
+        assert!(opts.renumber_lists);
+        assert!(opts.normalize_bullets);
+
+        assert!(Config::from_git_entries(&[entry("rule72.renumberlists", "2")]).is_err());
+    }
+
+    #[test]
+    fn test_reflow_setting() {
+        let config = Config::from_toml("reflow = \"minimal\"\n").unwrap();
+        assert_eq!(config.reflow, Some(ReflowMode::Minimal));
+        assert_eq!(
+            Config::from_toml("reflow = \"all\"\n").unwrap().reflow,
+            Some(ReflowMode::All)
+        );
+        assert!(Config::from_toml("reflow = \"some\"\n").is_err());
+
+        let config = Config::from_git_entries(&[entry("rule72.reflow", "minimal")]).unwrap();
+        let mut opts = Options::default();
+        config.apply(&mut opts);
+        assert_eq!(opts.reflow, ReflowMode::Minimal);
+
+        assert!(Config::from_git_entries(&[entry("rule72.reflow", "most")]).is_err());
+    }
+
+    #[test]
+    fn test_merge_precedence() {
+        let file = Config {
+            width: Some(80),
+            headline_width: Some(60),
+            ..Config::default()
+        };
+        let git = Config {
+            width: Some(100),
+            ..Config::default()
+        };
+
+        let merged = file.merge(git);
+        assert_eq!(merged.width, Some(100));
+        assert_eq!(merged.headline_width, Some(60));
+
+        let mut opts = Options::default();
+        merged.apply(&mut opts);
+        assert_eq!(opts.width, 100);
+        assert_eq!(opts.headline_width, 60);
+        assert_eq!(opts.comment_char, '#');
+    }
+
+    #[test]
+    fn test_find_config_file_stops_at_repo_root() {
+        let root = tempfile::tempdir().unwrap();
+        let repo = root.path().join("repo");
+        let nested = repo.join("src").join("deep");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir(repo.join(".git")).unwrap();
+
+        // A file above the repository root is not picked up
+        fs::write(root.path().join(CONFIG_FILE), "width = 1\n").unwrap();
+        assert_eq!(find_config_file(&nested), None);
+
+        fs::write(repo.join(CONFIG_FILE), "width = 80\n").unwrap();
+        assert_eq!(find_config_file(&nested), Some(repo.join(CONFIG_FILE)));
+    }
+}
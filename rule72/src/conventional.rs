@@ -0,0 +1,256 @@
+//! Conventional Commits: headline parsing and validation.
+//!
+//! Parses headlines of the form `type(scope)!: description` into a
+//! [`ConventionalHeadline`] and, when [`Options::conventional`] is set,
+//! validates them against the allowed types and scopes. A `!` breaking
+//! marker must be explained by a `BREAKING CHANGE:` footer.
+
+use crate::lint::{subject, Diagnostic, Severity};
+use crate::types::{Document, Options};
+use crate::utils::{is_breaking_change, trailer_token};
+
+/// Structured Conventional Commits headline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConventionalHeadline {
+    /// Commit type, e.g. `feat` or `fix`
+    pub commit_type: String,
+    /// Optional scope in parentheses, e.g. `auth` in `refactor(auth): ...`
+    pub scope: Option<String>,
+    /// Whether the `!` breaking change marker is present
+    pub breaking: bool,
+    pub description: String,
+}
+
+/// Parse a headline as `type(scope)!: description`; `None` if it does not follow the format.
+pub fn parse_headline(headline: &str) -> Option<ConventionalHeadline> {
+    let headline = headline.trim();
+    let type_len = headline
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(headline.len());
+    let commit_type = &headline[..type_len];
+    if !commit_type.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let mut rest = &headline[type_len..];
+    let mut scope = None;
+    if let Some(after_paren) = rest.strip_prefix('(') {
+        let close = after_paren.find(')')?;
+        let name = &after_paren[..close];
+        if name.trim().is_empty() || name.contains('(') {
+            return None;
+        }
+        scope = Some(name.to_string());
+        rest = &after_paren[close + 1..];
+    }
+
+    let breaking = rest.starts_with('!');
+    if breaking {
+        rest = &rest[1..];
+    }
+
+    let description = rest.strip_prefix(": ")?.trim();
+    if description.is_empty() {
+        return None;
+    }
+
+    Some(ConventionalHeadline {
+        commit_type: commit_type.to_string(),
+        scope,
+        breaking,
+        description: description.to_string(),
+    })
+}
+
+impl Document {
+    /// The headline parsed as a Conventional Commits headline, if it is one.
+    /// A first line not taken as the headline (e.g. `- add foo`) is still
+    /// the subject Git shows, so it is parsed as well.
+    pub fn conventional(&self) -> Option<ConventionalHeadline> {
+        parse_headline(&subject(self)?.text)
+    }
+
+    /// Whether a `BREAKING CHANGE:` (or `BREAKING-CHANGE:`) footer is present
+    pub fn has_breaking_change_footer(&self, separators: &str) -> bool {
+        self.footers
+            .iter()
+            .any(|footer| trailer_token(&footer.text, separators).is_some_and(is_breaking_change))
+    }
+}
+
+/// Check if a type or scope is in an allow-list. Like the rest of a
+/// Conventional Commits headline, they are compared case-insensitively.
+fn is_allowed(value: &str, allowed: &[String]) -> bool {
+    allowed.iter().any(|item| item.eq_ignore_ascii_case(value))
+}
+
+/// Validate the headline against the Conventional Commits format and the
+/// allowed types/scopes in `opts` (empty lists allow anything).
+pub fn validate(doc: &Document, opts: &Options) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let Some(headline) = subject(doc) else {
+        return diagnostics;
+    };
+
+    let Some(parsed) = doc.conventional() else {
+        diagnostics.push(Diagnostic::new(
+            headline,
+            Severity::Error,
+            "conventional-format",
+            "headline does not follow `type(scope)!: description`".to_string(),
+        ));
+        return diagnostics;
+    };
+
+    if !opts.conventional_types.is_empty()
+        && !is_allowed(&parsed.commit_type, &opts.conventional_types)
+    {
+        diagnostics.push(Diagnostic::new(
+            headline,
+            Severity::Error,
+            "conventional-type",
+            format!(
+                "type `{}` is not one of: {}",
+                parsed.commit_type,
+                opts.conventional_types.join(", ")
+            ),
+        ));
+    }
+
+    if let Some(scope) = &parsed.scope {
+        if !opts.conventional_scopes.is_empty() && !is_allowed(scope, &opts.conventional_scopes) {
+            diagnostics.push(Diagnostic::new(
+                headline,
+                Severity::Error,
+                "conventional-scope",
+                format!(
+                    "scope `{scope}` is not one of: {}",
+                    opts.conventional_scopes.join(", ")
+                ),
+            ));
+        }
+    }
+
+    if parsed.breaking && !doc.has_breaking_change_footer(&opts.trailer_separators) {
+        diagnostics.push(Diagnostic::new(
+            headline,
+            Severity::Error,
+            "conventional-breaking",
+            "`!` marks a breaking change but there is no `BREAKING CHANGE:` footer".to_string(),
+        ));
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_document;
+
+    #[test]
+    fn test_parse_headline() {
+        assert_eq!(
+            parse_headline("refactor(auth): simplify token refresh"),
+            Some(ConventionalHeadline {
+                commit_type: "refactor".to_string(),
+                scope: Some("auth".to_string()),
+                breaking: false,
+                description: "simplify token refresh".to_string(),
+            })
+        );
+
+        let parsed = parse_headline("feat!: drop v1 API").unwrap();
+        assert_eq!(parsed.commit_type, "feat");
+        assert_eq!(parsed.scope, None);
+        assert!(parsed.breaking);
+        assert_eq!(parsed.description, "drop v1 API");
+
+        assert!(
+            parse_headline("fix(api)!: reject empty ids")
+                .unwrap()
+                .breaking
+        );
+    }
+
+    #[test]
+    fn test_parse_headline_rejects_non_conventional() {
+        assert_eq!(parse_headline("Fix the scheduler race"), None);
+        assert_eq!(parse_headline("fix:missing space"), None);
+        assert_eq!(parse_headline("fix: "), None);
+        assert_eq!(parse_headline("fix(): empty scope"), None);
+        assert_eq!(parse_headline("fix(api: unclosed scope"), None);
+        assert_eq!(parse_headline("1fix: starts with a digit"), None);
+    }
+
+    fn codes(input: &str, opts: &Options) -> Vec<&'static str> {
+        validate(&parse_document(input, opts), opts)
+            .into_iter()
+            .map(|d| d.code)
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_types_and_scopes() {
+        let opts = Options {
+            conventional: true,
+            conventional_types: vec!["feat".to_string(), "fix".to_string()],
+            conventional_scopes: vec!["api".to_string()],
+            ..Options::default()
+        };
+
+        assert!(codes("fix(api): handle empty ids\n", &opts).is_empty());
+        assert!(codes("feat: add lint mode\n", &opts).is_empty());
+        assert_eq!(
+            codes("chore(ui): bump deps\n", &opts),
+            vec!["conventional-type", "conventional-scope"]
+        );
+        assert_eq!(codes("Bump deps\n", &opts), vec!["conventional-format"]);
+
+        // Compared case-insensitively, but otherwise exactly as listed
+        assert!(codes("FIX(Api): handle empty ids\n", &opts).is_empty());
+        let opts = Options {
+            conventional_types: vec!["feat:".to_string()],
+            ..opts
+        };
+        assert_eq!(
+            codes("feat: add lint mode\n", &opts),
+            vec!["conventional-type"]
+        );
+
+        // Empty lists allow any type and scope
+        assert!(codes("chore(ui): bump deps\n", &Options::default()).is_empty());
+    }
+
+    #[test]
+    fn test_validate_subject_not_prose() {
+        let opts = Options::default();
+        for input in [
+            "- add foo\n",
+            "    indented subject\n",
+            "x = foo(bar[0]);\n",
+            "\n# comment\n- add foo\n\nBody\n",
+        ] {
+            assert_eq!(
+                codes(input, &opts),
+                vec!["conventional-format"],
+                "{input:?}"
+            );
+        }
+        assert!(codes("    fix: indented subject\n", &opts).is_empty());
+    }
+
+    #[test]
+    fn test_validate_breaking_requires_footer() {
+        let opts = Options::default();
+        assert_eq!(
+            codes("feat!: drop v1 API\n\nThe old endpoints are gone.\n", &opts),
+            vec!["conventional-breaking"]
+        );
+        assert!(codes(
+            "feat!: drop v1 API\n\nThe old endpoints are gone.\n\nBREAKING CHANGE: /v1 is removed\n",
+            &opts
+        )
+        .is_empty());
+    }
+}
@@ -27,17 +27,7 @@ pub fn generate_debug_svg(doc: &Document, path: &str) {
 
     // Add body chunks
     for chunk in &doc.body_chunks {
-        match chunk {
-            ContChunk::Comment(lines)
-            | ContChunk::Table(lines)
-            | ContChunk::Code(lines)
-            | ContChunk::Paragraph(lines) => {
-                doc_lines.extend(lines.iter().cloned());
-            }
-            ContChunk::List(list_node) => {
-                collect_list_lines_for_svg(&mut doc_lines, list_node);
-            }
-        }
+        collect_chunk_lines_for_svg(&mut doc_lines, chunk);
     }
 
     // Add footers
@@ -51,35 +41,7 @@ pub fn generate_debug_svg(doc: &Document, path: &str) {
     }
 
     for chunk in &doc.body_chunks {
-        match chunk {
-            ContChunk::Comment(lines) => {
-                for line in lines {
-                    all_lines.push((line.clone(), 1, "comment"));
-                }
-            }
-            ContChunk::Table(lines) => {
-                for line in lines {
-                    all_lines.push((line.clone(), 1, "table"));
-                }
-            }
-            ContChunk::Code(lines) => {
-                for line in lines {
-                    all_lines.push((line.clone(), 1, "code"));
-                }
-            }
-            ContChunk::Paragraph(lines) => {
-                for line in lines {
-                    if line.final_category == Category::Empty {
-                        all_lines.push((line.clone(), 1, "empty"));
-                    } else {
-                        all_lines.push((line.clone(), 1, "paragraph"));
-                    }
-                }
-            }
-            ContChunk::List(list_node) => {
-                collect_list_lines_owned(&mut all_lines, list_node, 1);
-            }
-        }
+        collect_chunk_lines_owned(&mut all_lines, chunk, 1);
     }
 
     for footer in &doc.footers {
@@ -110,6 +72,7 @@ pub fn generate_debug_svg(doc: &Document, path: &str) {
     svg.push_str("    .code { fill: #b48ead; }\n");
     svg.push_str("    .paragraph { fill: #2e3440; }\n");
     svg.push_str("    .list { fill: #2e3440; }\n");
+    svg.push_str("    .quote { fill: #4c566a; }\n");
     svg.push_str("    .footer { fill: #4c566a; }\n");
     svg.push_str("    .empty { fill: #d8dee9; }\n");
     svg.push_str("    .chunk-rect { fill: none; stroke-width: 2; opacity: 0.5; }\n");
@@ -162,6 +125,7 @@ pub fn generate_debug_svg(doc: &Document, path: &str) {
             Category::Code => "#ff40ff",              // bright magenta
             Category::Table => "#00cccc",             // bright cyan
             Category::URL => "#40a0ff",               // light blue
+            Category::Quote => "#40a040",             // green
             Category::Empty => "#e0e0e0",             // light gray
             Category::Comment => "#808080",           // medium gray
             Category::Footer => "#606060",            // dark gray
@@ -236,6 +200,7 @@ Probabilities:
             "code" => "#b48ead",
             "paragraph" => "#a3be8c",
             "list" => "#81a1c1",
+            "quote" => "#ebcb8b",
             "footer" => "#bf616a",
             "empty" => "#d8dee9",
             _ => "#4c566a",
@@ -269,6 +234,31 @@ Probabilities:
     }
 }
 
+fn collect_chunk_lines_owned(
+    all_lines: &mut Vec<(CatLine, usize, &'static str)>,
+    chunk: &ContChunk,
+    depth: usize,
+) {
+    let (lines, class) = match chunk {
+        ContChunk::Comment(lines) => (lines, "comment"),
+        ContChunk::Table(lines) => (lines, "table"),
+        ContChunk::Code(lines) => (lines, "code"),
+        ContChunk::Quote(lines) => (lines, "quote"),
+        ContChunk::Paragraph(lines) => (lines, "paragraph"),
+        ContChunk::List(list_node) => {
+            collect_list_lines_owned(all_lines, list_node, depth);
+            return;
+        }
+    };
+    for line in lines {
+        if line.final_category == Category::Empty {
+            all_lines.push((line.clone(), depth, "empty"));
+        } else {
+            all_lines.push((line.clone(), depth, class));
+        }
+    }
+}
+
 fn collect_list_lines_owned(
     all_lines: &mut Vec<(CatLine, usize, &'static str)>,
     list: &ListNode,
@@ -288,8 +278,23 @@ fn collect_list_lines_owned(
         for cont in &item.continuation {
             all_lines.push((cont.clone(), depth + 1, "list"));
         }
-        if let Some(nested) = &item.nested {
-            collect_list_lines_owned(all_lines, nested, depth + 1);
+        for child in &item.children {
+            collect_chunk_lines_owned(all_lines, child, depth + 1);
+        }
+    }
+}
+
+fn collect_chunk_lines_for_svg(doc_lines: &mut Vec<CatLine>, chunk: &ContChunk) {
+    match chunk {
+        ContChunk::Comment(lines)
+        | ContChunk::Table(lines)
+        | ContChunk::Code(lines)
+        | ContChunk::Quote(lines)
+        | ContChunk::Paragraph(lines) => {
+            doc_lines.extend(lines.iter().cloned());
+        }
+        ContChunk::List(list_node) => {
+            collect_list_lines_for_svg(doc_lines, list_node);
         }
     }
 }
@@ -301,8 +306,8 @@ fn collect_list_lines_for_svg(doc_lines: &mut Vec<CatLine>, list: &ListNode) {
     for item in &list.items {
         doc_lines.push(item.bullet_line.clone());
         doc_lines.extend(item.continuation.iter().cloned());
-        if let Some(nested) = &item.nested {
-            collect_list_lines_for_svg(doc_lines, nested);
+        for child in &item.children {
+            collect_chunk_lines_for_svg(doc_lines, child);
         }
     }
 }
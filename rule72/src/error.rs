@@ -0,0 +1,31 @@
+//! Error type for the fallible library API.
+//!
+//! [`try_reflow`](crate::try_reflow) reports problems through [`Error`] so
+//! callers can fall back to the unmodified message instead of crashing, as
+//! the PRD requires for Git hooks.
+
+use std::fmt;
+
+/// Errors returned by [`try_reflow`](crate::try_reflow)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The [`Options`](crate::Options) cannot produce a valid layout, e.g. a zero width
+    InvalidOptions(String),
+    /// An internal invariant failed while formatting; this is a bug in rule72.
+    /// Only reported if the panic could be caught (not with `panic = "abort"`).
+    Internal(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidOptions(reason) => write!(f, "invalid options: {reason}"),
+            Error::Internal(reason) => write!(f, "internal error: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Result type of the fallible library API
+pub type Result<T> = std::result::Result<T, Error>;
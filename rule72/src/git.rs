@@ -0,0 +1,68 @@
+//! Thin wrappers around the local `git` binary.
+//!
+//! Used by the CLI to enumerate commits in a revision range and fetch their
+//! raw messages, so that a whole branch can be checked in one invocation,
+//! and to read repository configuration such as `core.commentChar`.
+
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+/// Run `git` with the given arguments in the current directory and return stdout.
+fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("failed to run git")?;
+
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// List the commits in a revision range (e.g. `origin/main..HEAD`), oldest first.
+/// A range starting with `-` is taken as a revision, not as an option.
+pub fn rev_list(range: &str) -> Result<Vec<String>> {
+    let stdout = git(&["rev-list", "--reverse", "--end-of-options", range])?;
+    Ok(stdout.lines().map(str::to_string).collect())
+}
+
+/// Fetch the raw, unmodified message of a commit.
+///
+/// Uses `git cat-file` rather than `git log --format=%B` so that no trailing
+/// newlines are added or stripped.
+pub fn commit_message(rev: &str) -> Result<String> {
+    let raw = git(&["cat-file", "commit", rev])?;
+    match raw.split_once("\n\n") {
+        Some((_headers, message)) => Ok(message.to_string()),
+        None => Ok(String::new()),
+    }
+}
+
+/// Read a git config value, returning `None` if it is unset or git fails.
+pub fn config_get(key: &str) -> Option<String> {
+    git(&["config", "--get", key])
+        .ok()
+        .map(|value| value.trim_end_matches('\n').to_string())
+}
+
+/// List git config entries whose key matches `pattern` as `(key, value)` pairs.
+/// Returns an empty list if nothing matches or git is unavailable.
+pub fn config_get_regexp(pattern: &str) -> Vec<(String, String)> {
+    let stdout = match git(&["config", "--get-regexp", pattern]) {
+        Ok(stdout) => stdout,
+        Err(_) => return Vec::new(),
+    };
+    stdout
+        .lines()
+        .map(|line| match line.split_once(' ') {
+            Some((key, value)) => (key.to_string(), value.to_string()),
+            None => (line.to_string(), String::new()),
+        })
+        .collect()
+}
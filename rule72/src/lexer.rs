@@ -3,49 +3,76 @@
 //! This module performs the first stage of processing, analyzing each line
 //! individually to assign initial probability scores to different categories
 //! (prose, list, code, table, etc.) based on content patterns and indentation.
+//! Fenced code blocks and the trailing trailer block are the only constructs
+//! that need state across lines. Lines starting with `>` markers followed by
+//! a space or the end of the line are quotations.
 
 use std::collections::HashMap;
 
 use crate::types::{CatLine, Category, Options};
-use crate::utils::{count_indent, count_special_chars, debug_trace, is_footer_line, is_list_item};
+use crate::utils::{
+    count_indent, debug_trace, fence_marker, has_footer_tag, is_breaking_change, is_closing_fence,
+    is_comment_line, is_footer_line, is_list_item, is_listed_tag, is_quote_line, looks_like_code,
+    trailer_token,
+};
 
 /// Lexer: convert raw lines to CatLines with initial probabilities
 pub fn lex_lines(lines: &[&str], opts: &Options) -> Vec<CatLine> {
     debug_trace!(opts, "=== LEXER PHASE ===");
     debug_trace!(opts, "Processing {} input lines", lines.len());
 
-    lines
+    // Open fence (character, run length) while inside a fenced code block
+    let mut open_fence: Option<(char, usize)> = None;
+    // Lines belonging to fenced code blocks, which can never be trailers
+    let mut fenced = Vec::with_capacity(lines.len());
+
+    let mut cat_lines: Vec<CatLine> = lines
         .iter()
         .enumerate()
         .map(|(idx, line)| {
             debug_trace!(opts, "Line {}: {:?}", idx + 1, line);
-            let mut probabilities = HashMap::new();
+            let mut probabilities: HashMap<Category, f32> = HashMap::new();
             let indent = count_indent(line);
             let trimmed = line.trim();
             debug_trace!(opts, "  Indent: {}, Trimmed: {:?}", indent, trimmed);
 
+            fenced.push(open_fence.is_some() || fence_marker(trimmed).is_some());
+
             // Initial probabilities based on content patterns
-            if trimmed.is_empty() {
+            if let Some((fence_char, run)) = open_fence {
+                // Everything up to and including the closing fence is verbatim code
+                if is_closing_fence(trimmed, fence_char, run) {
+                    debug_trace!(opts, "  Closing {}-fence", fence_char);
+                    open_fence = None;
+                }
+                probabilities.insert(Category::Code, 1.0);
+            } else if let Some(fence) = fence_marker(trimmed) {
+                debug_trace!(opts, "  Opening {}-fence", fence.0);
+                open_fence = Some(fence);
+                probabilities.insert(Category::Code, 1.0);
+            } else if trimmed.is_empty() {
                 probabilities.insert(Category::Empty, 1.0);
-            } else if trimmed.starts_with('#') || trimmed.starts_with("//") {
+            } else if is_comment_line(trimmed, opts.comment_char) {
                 probabilities.insert(Category::Comment, 0.9);
                 probabilities.insert(Category::ProseGeneral, 0.1);
+            } else if indent < 4 && is_quote_line(trimmed) {
+                probabilities.insert(Category::Quote, 0.9);
+                probabilities.insert(Category::ProseGeneral, 0.1);
             } else if trimmed.starts_with('|') && trimmed.ends_with('|') {
                 probabilities.insert(Category::Table, 0.8);
                 probabilities.insert(Category::Code, 0.2);
-            } else if trimmed.starts_with("http") || trimmed.contains("://") {
+            } else if trimmed.starts_with("http")
+                || (trimmed.contains("://") && !is_list_item(trimmed))
+            {
                 probabilities.insert(Category::URL, 0.9);
                 probabilities.insert(Category::ProseGeneral, 0.1);
-            } else if is_footer_line(trimmed) {
+            } else if is_known_footer(trimmed, opts) {
                 probabilities.insert(Category::Footer, 0.9);
                 probabilities.insert(Category::ProseGeneral, 0.1);
             } else if is_list_item(trimmed) {
                 probabilities.insert(Category::List, 0.92);
                 probabilities.insert(Category::ProseGeneral, 0.08);
-            } else if indent >= 4
-                || (!trimmed.is_empty()
-                    && count_special_chars(trimmed) as f32 / trimmed.len() as f32 > 0.3)
-            {
+            } else if indent >= 4 || looks_like_code(trimmed) {
                 probabilities.insert(Category::Code, 0.77);
                 probabilities.insert(Category::ProseGeneral, 0.23);
             } else if idx == 0 {
@@ -61,7 +88,7 @@ pub fn lex_lines(lines: &[&str], opts: &Options) -> Vec<CatLine> {
             // Find the most likely category
             let final_category = probabilities
                 .iter()
-                .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+                .max_by(|a, b| a.1.total_cmp(b.1).then_with(|| b.0.cmp(a.0)))
                 .map(|(cat, _)| *cat)
                 .unwrap_or(Category::ProseGeneral);
 
@@ -75,7 +102,99 @@ pub fn lex_lines(lines: &[&str], opts: &Options) -> Vec<CatLine> {
                 final_category,
             }
         })
-        .collect()
+        .collect();
+
+    mark_trailer_block(&mut cat_lines, &fenced, opts);
+    cat_lines
+}
+
+/// Check if a line starts with a built-in or user-supplied footer tag that
+/// is not on the deny list. Such lines are footers wherever they appear.
+fn is_known_footer(trimmed: &str, opts: &Options) -> bool {
+    (is_footer_line(trimmed) || has_footer_tag(trimmed, &opts.footer_tags))
+        && !trailer_token(trimmed, &opts.trailer_separators)
+            .is_some_and(|token| is_listed_tag(token, &opts.footer_deny))
+}
+
+/// Check if a line starting with `trimmed` would be lexed as the start of
+/// a construct of its own (fence, comment, quote, table, footer or list item)
+/// rather than as prose. The pretty printer never wraps text onto such a
+/// line, so that formatted output reads back with the same structure.
+pub(crate) fn opens_block(trimmed: &str, opts: &Options) -> bool {
+    fence_marker(trimmed).is_some()
+        || is_comment_line(trimmed, opts.comment_char)
+        || is_quote_line(trimmed)
+        || trimmed.starts_with('|')
+        || is_known_footer(trimmed, opts)
+        || is_list_item(trimmed)
+}
+
+/// Apply Git's trailer rules to the last paragraph of the message.
+///
+/// Like `git interpret-trailers`, the last paragraph is a trailer block if all
+/// of its lines are `Token: value` trailers (or their indented continuations),
+/// or if at least 25% are and one of them is a recognised footer tag or a
+/// `BREAKING CHANGE`, whose value may run on over unindented lines. Generic
+/// trailer-looking lines elsewhere, such as "EN: something broke", stay prose.
+fn mark_trailer_block(cat_lines: &mut [CatLine], fenced: &[bool], opts: &Options) {
+    // Skip trailing blank lines and comments (e.g. the commit template)
+    let mut end = cat_lines.len();
+    while end > 0
+        && matches!(
+            cat_lines[end - 1].final_category,
+            Category::Empty | Category::Comment
+        )
+    {
+        end -= 1;
+    }
+    let mut start = end;
+    while start > 0 && cat_lines[start - 1].final_category != Category::Empty {
+        start -= 1;
+    }
+
+    // The subject paragraph and fenced code are never trailer blocks
+    if start == 0 || start == end || fenced[start..end].iter().any(|&f| f) {
+        return;
+    }
+
+    let mut trailers = 0;
+    let mut others = 0;
+    let mut recognised = false;
+    let mut after_trailer = false;
+    for line in &cat_lines[start..end] {
+        let trimmed = line.text.trim();
+        let token = trailer_token(trimmed, &opts.trailer_separators)
+            .filter(|token| !is_listed_tag(token, &opts.footer_deny));
+
+        if line.final_category == Category::Comment || (after_trailer && line.indent > 0) {
+            // Comments and folded trailer values do not count either way
+        } else if token.is_some() {
+            trailers += 1;
+            recognised |= is_known_footer(trimmed, opts) || token.is_some_and(is_breaking_change);
+            after_trailer = true;
+        } else if matches!(
+            line.final_category,
+            Category::List | Category::Code | Category::Table | Category::Quote
+        ) {
+            // Structured body content means this is not a trailer block
+            return;
+        } else {
+            others += 1;
+            after_trailer = false;
+        }
+    }
+
+    if trailers == 0 || (others > 0 && !(recognised && trailers * 3 >= others)) {
+        return;
+    }
+
+    for line in &mut cat_lines[start..end] {
+        if line.final_category != Category::Comment {
+            line.probabilities =
+                HashMap::from([(Category::Footer, 0.9), (Category::ProseGeneral, 0.1)]);
+            line.final_category = Category::Footer;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -101,6 +220,7 @@ mod tests {
             headline_width: 50,
             debug_svg: None,
             debug_trace: false,
+            ..Options::default()
         };
         let cat_lines = lex_lines(&lines, &opts);
 
@@ -147,7 +267,8 @@ mod tests {
 
         assert_eq!(cat_lines[0].final_category, Category::ProseGeneral);
         assert_eq!(cat_lines[1].final_category, Category::Comment);
-        assert_eq!(cat_lines[2].final_category, Category::Comment);
+        // Git only strips lines starting with the comment character
+        assert_ne!(cat_lines[2].final_category, Category::Comment);
         // Block comment should be prose or code, not comment (our pattern is specific)
         assert_ne!(cat_lines[3].final_category, Category::Comment);
     }
@@ -182,6 +303,7 @@ mod tests {
             "1. Numbered item",
             "2) Paren numbered",
             "10. Double digit",
+            "- See https://example.com/design",
             "🔥 Emoji bullet",
             "✅ Check emoji",
         ];
@@ -190,11 +312,36 @@ mod tests {
         let cat_lines = lex_lines(&lines, &opts);
 
         assert_eq!(cat_lines[0].final_category, Category::ProseGeneral);
-        for i in 1..cat_lines.len() {
-            assert_eq!(cat_lines[i].final_category, Category::List);
+        for line in cat_lines.iter().skip(1) {
+            assert_eq!(line.final_category, Category::List);
         }
     }
 
+    #[test]
+    fn test_lexer_quotes() {
+        let lines = vec![
+            "Subject line",
+            "> quoted review comment",
+            ">> nested reply",
+            "> > nested with spaces",
+            ">",
+            "  > indented quote",
+            "    > indented code",
+            ">=3.0 of libfoo is now required",
+            ">>= shifts the value right",
+        ];
+
+        let opts = Options::default();
+        let cat_lines = lex_lines(&lines, &opts);
+
+        for line in &cat_lines[1..6] {
+            assert_eq!(line.final_category, Category::Quote, "{:?}", line.text);
+        }
+        assert_eq!(cat_lines[6].final_category, Category::Code);
+        assert_eq!(cat_lines[7].final_category, Category::ProseGeneral);
+        assert_ne!(cat_lines[8].final_category, Category::Quote);
+    }
+
     #[test]
     fn test_lexer_tables() {
         let lines = vec![
@@ -209,8 +356,8 @@ mod tests {
         let cat_lines = lex_lines(&lines, &opts);
 
         assert_eq!(cat_lines[0].final_category, Category::ProseGeneral);
-        for i in 1..cat_lines.len() {
-            assert_eq!(cat_lines[i].final_category, Category::Table);
+        for line in cat_lines.iter().skip(1) {
+            assert_eq!(line.final_category, Category::Table);
         }
     }
 
@@ -230,8 +377,8 @@ mod tests {
         let cat_lines = lex_lines(&lines, &opts);
 
         assert_eq!(cat_lines[0].final_category, Category::ProseGeneral);
-        for i in 1..cat_lines.len() {
-            assert_eq!(cat_lines[i].final_category, Category::Footer);
+        for line in cat_lines.iter().skip(1) {
+            assert_eq!(line.final_category, Category::Footer);
         }
     }
 
@@ -309,4 +456,147 @@ mod tests {
         let total: f32 = probabilities.values().sum();
         assert!(total > 0.0);
     }
+
+    #[test]
+    fn test_lexer_fenced_code() {
+        let lines = vec![
+            "Subject line",
+            "",
+            "```rust",
+            "This looks like an ordinary sentence.",
+            "",
+            "- not a list item",
+            "Signed-off-by: not a footer",
+            "```",
+            "Back to prose",
+        ];
+
+        let opts = Options::default();
+        let cat_lines = lex_lines(&lines, &opts);
+
+        for line in &cat_lines[2..8] {
+            assert_eq!(line.final_category, Category::Code, "{:?}", line.text);
+        }
+        assert_eq!(cat_lines[8].final_category, Category::ProseGeneral);
+    }
+
+    #[test]
+    fn test_lexer_tilde_fence_needs_matching_close() {
+        let lines = vec!["Subject", "~~~~", "prose", "~~~", "```", "~~~~", "after"];
+
+        let opts = Options::default();
+        let cat_lines = lex_lines(&lines, &opts);
+
+        // Shorter tilde run and backtick fence do not close a four-tilde fence
+        for line in &cat_lines[1..6] {
+            assert_eq!(line.final_category, Category::Code, "{:?}", line.text);
+        }
+        assert_eq!(cat_lines[6].final_category, Category::ProseGeneral);
+    }
+
+    #[test]
+    fn test_lexer_custom_comment_char() {
+        let lines = vec![
+            "Subject line",
+            "; Please enter the commit message",
+            "# Heading",
+            "#123 was fixed upstream",
+        ];
+
+        let opts = Options {
+            comment_char: ';',
+            ..Options::default()
+        };
+        let cat_lines = lex_lines(&lines, &opts);
+
+        assert_eq!(cat_lines[1].final_category, Category::Comment);
+        assert_ne!(cat_lines[2].final_category, Category::Comment);
+        assert_ne!(cat_lines[3].final_category, Category::Comment);
+    }
+
+    #[test]
+    fn test_lexer_trailer_block() {
+        let lines = vec![
+            "Subject line",
+            "",
+            "EN: something broke in the middle of the body",
+            "",
+            "Change-Id: I8473b95934b5732ac55d26311a706c9c2bde9940",
+            "Bug: 1234",
+            "Link: https://example.com/review/1234",
+            "  continued link description",
+            "# Please enter the commit message for your changes.",
+        ];
+
+        let opts = Options::default();
+        let cat_lines = lex_lines(&lines, &opts);
+
+        assert_ne!(cat_lines[2].final_category, Category::Footer);
+        for line in &cat_lines[4..8] {
+            assert_eq!(line.final_category, Category::Footer, "{:?}", line.text);
+        }
+        assert_eq!(cat_lines[8].final_category, Category::Comment);
+    }
+
+    #[test]
+    fn test_lexer_trailer_block_mixed() {
+        // A recognised tag lets git accept a block that is at least 25% trailers
+        let lines = vec![
+            "Subject line",
+            "",
+            "Body",
+            "",
+            "(cherry picked from commit abc)",
+            "Signed-off-by: Author <email>",
+        ];
+        let cat_lines = lex_lines(&lines, &Options::default());
+        assert_eq!(cat_lines[4].final_category, Category::Footer);
+        assert_eq!(cat_lines[5].final_category, Category::Footer);
+
+        // Without a recognised tag, prose keeps a generic trailer out
+        let lines = vec!["Subject line", "", "Note: this is prose", "and so is this"];
+        let cat_lines = lex_lines(&lines, &Options::default());
+        assert_ne!(cat_lines[2].final_category, Category::Footer);
+    }
+
+    #[test]
+    fn test_lexer_trailer_options() {
+        let lines = vec![
+            "Subject line",
+            "",
+            "Body",
+            "",
+            "EN: something broke",
+            "Fixes #123",
+        ];
+
+        let cat_lines = lex_lines(&lines, &Options::default());
+        assert_ne!(cat_lines[4].final_category, Category::Footer);
+
+        let opts = Options {
+            trailer_separators: ":#".to_string(),
+            ..Options::default()
+        };
+        let cat_lines = lex_lines(&lines, &opts);
+        assert_eq!(cat_lines[4].final_category, Category::Footer);
+        assert_eq!(cat_lines[5].final_category, Category::Footer);
+
+        let opts = Options {
+            trailer_separators: ":#".to_string(),
+            footer_deny: vec!["EN".to_string()],
+            ..Options::default()
+        };
+        let cat_lines = lex_lines(&lines, &opts);
+        assert_ne!(cat_lines[4].final_category, Category::Footer);
+    }
+
+    #[test]
+    fn test_lexer_trailer_block_not_subject_or_fence() {
+        let cat_lines = lex_lines(&["Fix: the subject"], &Options::default());
+        assert_ne!(cat_lines[0].final_category, Category::Footer);
+
+        let lines = vec!["Subject", "", "```", "Key: value", "```"];
+        let cat_lines = lex_lines(&lines, &Options::default());
+        assert_eq!(cat_lines[3].final_category, Category::Code);
+    }
 }
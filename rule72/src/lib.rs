@@ -8,6 +8,7 @@
 //! 2. **Context Refinement**: Use 4-point FIR-like kernel on neighbors
 //! 3. **Document Building**: Group lines into semantic chunks
 //! 4. **Pretty Printing**: Format each chunk type appropriately
+//! 5. **Verification**: Fall back to the input if any text was lost
 //!
 //! ## Example
 //! ```rust
@@ -16,39 +17,87 @@
 //! let input = "Very long commit message that needs to be wrapped...";
 //! let opts = Options::default();
 //! let output = reflow(input, &opts);
+//!
+//! // Or observe failures instead of falling back to the input
+//! let output = rule72::try_reflow(input, &opts).unwrap();
 //! ```
 
+use std::panic;
+
+use utils::debug_trace;
+
 // Public modules
+pub mod check;
 pub mod classifier;
+pub mod config;
+pub mod conventional;
 pub mod debug;
+pub mod error;
+pub mod git;
 pub mod lexer;
+pub mod lint;
 pub mod pretty_printer;
 pub mod tree_builder;
 pub mod types;
 pub mod utils;
+pub mod verify;
 
 // Re-export public API types
-pub use types::{CatLine, Category, ContChunk, Document, ListItem, ListNode, Options};
+pub use types::{
+    CatLine, Category, ContChunk, Document, ListItem, ListNode, Options, ReflowMode, WrapAlgorithm,
+};
 
 // Re-export main functions
+pub use check::check;
 pub use classifier::classify_with_context;
+pub use conventional::ConventionalHeadline;
 pub use debug::generate_debug_svg;
+pub use error::Error;
 pub use lexer::lex_lines;
+pub use lint::{lint, Diagnostic, Severity};
 pub use pretty_printer::pretty_print;
 pub use tree_builder::build_document;
 
-/// Public API: reflow an entire commit message
+/// Public API: reflow an entire commit message.
+///
+/// Never fails: if the options are invalid or formatting hits an internal
+/// error, the input is returned unchanged. Use [`try_reflow`] to observe
+/// the error instead.
 pub fn reflow(input: &str, opts: &Options) -> String {
-    let lines: Vec<&str> = input.lines().map(|l| l.trim_end_matches('\r')).collect();
+    try_reflow(input, opts).unwrap_or_else(|_| input.to_string())
+}
 
-    // Lex lines into CatLines
-    let cat_lines = lex_lines(&lines, opts);
+/// Public API: reflow an entire commit message, reporting invalid options
+/// and internal failures (including panics) as an [`Error`].
+///
+/// Options that cannot produce any layout are rejected up front by
+/// [`Options::validate`]. Narrow widths, even below the width of a list
+/// marker, are not an error: tokens that do not fit stay on a line of
+/// their own. Catching panics is only a last resort against bugs in the
+/// formatter. The panic hook still runs, so the default hook prints the
+/// panic message to stderr, and when built with `panic = "abort"` the
+/// process aborts instead of returning [`Error::Internal`].
+pub fn try_reflow(input: &str, opts: &Options) -> error::Result<String> {
+    opts.validate()?;
+    panic::catch_unwind(|| reflow_unchecked(input, opts)).map_err(|payload| {
+        let reason = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "formatter panicked".to_string());
+        Error::Internal(reason)
+    })
+}
 
-    // Apply context-aware classification
-    let classified_lines = classify_with_context(cat_lines);
+fn reflow_unchecked(input: &str, opts: &Options) -> String {
+    // Everything from the scissors line on (the `git commit -v` diff) is
+    // ignored by Git and passed through untouched
+    let (message, verbatim_tail) = utils::split_at_scissors(input, opts.comment_char);
+    if message.is_empty() && !verbatim_tail.is_empty() {
+        return verbatim_tail.to_string();
+    }
 
-    // Build document structure
-    let document = build_document(classified_lines);
+    let document = parse_document(message, opts);
 
     // Generate debug SVG if requested
     if let Some(svg_path) = &opts.debug_svg {
@@ -56,7 +105,32 @@ pub fn reflow(input: &str, opts: &Options) -> String {
     }
 
     // Pretty print the document
-    pretty_print(&document, opts)
+    let output = pretty_print(&document, opts);
+
+    // Safety net: never lose or invent content, at worst copy through unchanged
+    if !verify::same_content(message, &output, opts) {
+        debug_trace!(
+            opts,
+            "WARNING: reflowed text does not contain the same tokens as the input; keeping the original"
+        );
+        return input.to_string();
+    }
+
+    output + verbatim_tail
+}
+
+/// Public API: parse a commit message (without scissors tail) into its document structure
+pub fn parse_document(message: &str, opts: &Options) -> Document {
+    let lines: Vec<&str> = message.lines().map(|l| l.trim_end_matches('\r')).collect();
+
+    // Lex lines into CatLines
+    let cat_lines = lex_lines(&lines, opts);
+
+    // Apply context-aware classification
+    let classified_lines = classify_with_context(cat_lines);
+
+    // Build document structure
+    build_document(classified_lines)
 }
 
 #[cfg(test)]
@@ -72,6 +146,7 @@ mod tests {
             headline_width: 50,
             debug_svg: None,
             debug_trace: false,
+            ..Options::default()
         };
 
         let output = reflow(input, &opts);
@@ -80,4 +155,51 @@ mod tests {
         assert!(output.contains("Signed-off-by:"));
     }
 
+    #[test]
+    fn test_try_reflow_invalid_options() {
+        let input = "Subject line\n\nBody\n";
+        let opts = Options {
+            width: 0,
+            ..Options::default()
+        };
+
+        assert!(matches!(
+            try_reflow(input, &opts),
+            Err(Error::InvalidOptions(_))
+        ));
+        // The infallible API falls back to the unchanged input
+        assert_eq!(reflow(input, &opts), input);
+    }
+
+    #[test]
+    fn test_try_reflow_narrow_width() {
+        // Bullets wider than the width must not underflow
+        let input = "Subject\n\n-   a list item that needs wrapping\n";
+        let opts = Options {
+            width: 2,
+            ..Options::default()
+        };
+
+        let output = try_reflow(input, &opts).unwrap();
+        assert!(output.contains("-   a\n"));
+    }
+
+    #[test]
+    fn test_scissors_passthrough() {
+        let tail = "# ------------------------ >8 ------------------------\n# Do not modify or remove the line above.\ndiff --git a/f b/f\n+A very long added line in the diff that must not be wrapped by the formatter at all   \n";
+        let input = format!("Subject line\n\nBody\n{tail}");
+
+        let output = reflow(&input, &Options::default());
+        assert_eq!(output, format!("Subject line\n\nBody\n{tail}"));
+
+        // A message that is only the diff stays as is
+        assert_eq!(reflow(tail, &Options::default()), tail);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        // Like any other message, the empty one ends with a newline
+        assert_eq!(reflow("", &Options::default()), "\n");
+        assert_eq!(reflow("\n", &Options::default()), "\n");
+    }
 }
@@ -0,0 +1,357 @@
+//! Lint mode: report style problems instead of rewriting.
+//!
+//! Walks the parsed [`Document`] and produces [`Diagnostic`]s for issues the
+//! formatter either cannot fix (over-long headlines, unbreakable tokens) or
+//! that a reviewer would flag (trailing whitespace, mixed bullet styles).
+//! Each diagnostic points at the original line so editors and CI can
+//! annotate it. With [`Options::conventional`] the headline is also
+//! validated as a Conventional Commit (see [`crate::conventional`]).
+
+use std::fmt;
+
+use crate::conventional;
+use crate::parse_document;
+use crate::types::{CatLine, Category, ContChunk, Document, ListNode, Options};
+use crate::utils::{
+    display_width, extract_bullet_prefix, prefix_width, quote_prefix, segments, split_at_scissors,
+};
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A single lint finding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// 1-based line number in the original message
+    pub line_number: usize,
+    pub severity: Severity,
+    /// Stable identifier of the rule, e.g. `headline-length`
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(
+        line: &CatLine,
+        severity: Severity,
+        code: &'static str,
+        message: String,
+    ) -> Self {
+        Self {
+            line_number: line.line_number + 1,
+            severity,
+            code,
+            message,
+        }
+    }
+}
+
+/// Lint a commit message; everything from the scissors line on is ignored.
+pub fn lint(input: &str, opts: &Options) -> Vec<Diagnostic> {
+    let (message, _) = split_at_scissors(input, opts.comment_char);
+    lint_document(&parse_document(message, opts), opts)
+}
+
+/// Lint an already parsed document, returning diagnostics ordered by line
+pub fn lint_document(doc: &Document, opts: &Options) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    if let Some(headline) = subject(doc) {
+        lint_headline(doc, headline, opts, &mut diagnostics);
+    }
+    if opts.conventional {
+        diagnostics.extend(conventional::validate(doc, opts));
+    }
+
+    for chunk in &doc.body_chunks {
+        match chunk {
+            ContChunk::Paragraph(lines) => lint_paragraph(lines, opts, &mut diagnostics),
+            ContChunk::Quote(lines) => lint_quote(lines, opts, &mut diagnostics),
+            ContChunk::List(list) => lint_list(list, opts, &mut diagnostics),
+            ContChunk::Code(_) | ContChunk::Table(_) | ContChunk::Comment(_) => {}
+        }
+    }
+
+    for line in all_lines(doc) {
+        if line.text != line.text.trim_end() {
+            diagnostics.push(Diagnostic::new(
+                line,
+                Severity::Warning,
+                "trailing-whitespace",
+                "trailing whitespace".to_string(),
+            ));
+        }
+    }
+
+    diagnostics.sort_by_key(|d| d.line_number);
+    diagnostics
+}
+
+fn lint_headline(
+    doc: &Document,
+    headline: &CatLine,
+    opts: &Options,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let text = headline.text.trim_end();
+    let width = display_width(text);
+    if width > opts.headline_width {
+        diagnostics.push(Diagnostic::new(
+            headline,
+            Severity::Warning,
+            "headline-length",
+            format!(
+                "headline is {width} columns, longer than {}",
+                opts.headline_width
+            ),
+        ));
+    }
+
+    if text.ends_with('.') && !text.ends_with("..") {
+        diagnostics.push(Diagnostic::new(
+            headline,
+            Severity::Warning,
+            "headline-period",
+            "headline ends with a period".to_string(),
+        ));
+    }
+
+    // Git treats everything up to the first blank line as the subject
+    let next = all_lines(doc)
+        .into_iter()
+        .find(|line| line.line_number == headline.line_number + 1);
+    if let Some(next) = next {
+        if !matches!(next.final_category, Category::Empty | Category::Comment) {
+            diagnostics.push(Diagnostic::new(
+                next,
+                Severity::Error,
+                "headline-blank-line",
+                "missing blank line after the headline".to_string(),
+            ));
+        }
+    }
+}
+
+fn lint_list(list: &ListNode, opts: &Options, diagnostics: &mut Vec<Diagnostic>) {
+    let mut first_marker: Option<&str> = None;
+
+    for item in &list.items {
+        let prefix = extract_bullet_prefix(&item.bullet_line.text);
+        // The bullet itself, without a task-list checkbox
+        let marker = prefix.split_whitespace().next().unwrap_or_default();
+        if matches!(marker, "-" | "*" | "+") {
+            match first_marker {
+                None => first_marker = Some(marker),
+                Some(first) if first != marker => diagnostics.push(Diagnostic::new(
+                    &item.bullet_line,
+                    Severity::Warning,
+                    "mixed-bullets",
+                    format!("bullet `{marker}` differs from `{first}` used earlier in this list"),
+                )),
+                Some(_) => {}
+            }
+        }
+
+        let available = opts.width.saturating_sub(display_width(prefix));
+        lint_long_line(&item.bullet_line, opts.width, available, diagnostics);
+        for line in &item.continuation {
+            lint_long_line(line, opts.width, available, diagnostics);
+        }
+        for child in &item.children {
+            match child {
+                ContChunk::Paragraph(lines) => lint_paragraph(lines, opts, diagnostics),
+                ContChunk::Quote(lines) => lint_quote(lines, opts, diagnostics),
+                ContChunk::List(nested) => lint_list(nested, opts, diagnostics),
+                ContChunk::Code(_) | ContChunk::Table(_) | ContChunk::Comment(_) => {}
+            }
+        }
+    }
+}
+
+fn lint_paragraph(lines: &[CatLine], opts: &Options, diagnostics: &mut Vec<Diagnostic>) {
+    // Paragraphs wrap at the deeper of their first-line and hanging indent
+    let indent = lines.iter().take(2).map(|l| l.indent).max().unwrap_or(0);
+    let available = opts.width.saturating_sub(indent);
+    for line in lines {
+        lint_long_line(line, opts.width, available, diagnostics);
+    }
+}
+
+fn lint_quote(lines: &[CatLine], opts: &Options, diagnostics: &mut Vec<Diagnostic>) {
+    for line in lines {
+        let prefix = quote_prefix(&line.text);
+        let available = opts.width.saturating_sub(prefix_width(prefix));
+        lint_long_line(line, opts.width, available, diagnostics);
+    }
+}
+
+/// Flag a line over `width` containing a token wider than the `available`
+/// columns, which no amount of wrapping can fit
+fn lint_long_line(
+    line: &CatLine,
+    width: usize,
+    available: usize,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let line_width = display_width(line.text.trim_end());
+    if line_width <= width {
+        return;
+    }
+    if segments(&line.text)
+        .iter()
+        .any(|segment| segment.width > available)
+    {
+        diagnostics.push(Diagnostic::new(
+            line,
+            Severity::Warning,
+            "long-line",
+            format!("line is {line_width} columns, longer than {width}, and cannot be wrapped"),
+        ));
+    }
+}
+
+/// The line Git uses as the subject: the headline, or the first line that
+/// is neither blank nor a comment if the tree builder did not take it as
+/// one (e.g. a first line that looks like a list item or code)
+pub(crate) fn subject(doc: &Document) -> Option<&CatLine> {
+    doc.headline.as_ref().or_else(|| {
+        all_lines(doc)
+            .into_iter()
+            .find(|line| !matches!(line.final_category, Category::Empty | Category::Comment))
+    })
+}
+
+/// Every line of the document in original order
+fn all_lines(doc: &Document) -> Vec<&CatLine> {
+    fn chunk_lines<'a>(chunk: &'a ContChunk, out: &mut Vec<&'a CatLine>) {
+        match chunk {
+            ContChunk::Table(lines)
+            | ContChunk::Paragraph(lines)
+            | ContChunk::Code(lines)
+            | ContChunk::Quote(lines)
+            | ContChunk::Comment(lines) => out.extend(lines),
+            ContChunk::List(list) => {
+                out.extend(&list.introduction);
+                for item in &list.items {
+                    out.push(&item.bullet_line);
+                    out.extend(&item.continuation);
+                    for child in &item.children {
+                        chunk_lines(child, out);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut lines: Vec<&CatLine> = doc.headline.iter().collect();
+    for chunk in &doc.body_chunks {
+        chunk_lines(chunk, &mut lines);
+    }
+    lines.extend(&doc.footers);
+    lines.sort_by_key(|line| line.line_number);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(input: &str) -> Vec<(usize, &'static str)> {
+        lint(input, &Options::default())
+            .into_iter()
+            .map(|d| (d.line_number, d.code))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_clean_message() {
+        let input =
+            "Add lint subcommand\n\nExplain why the change is needed.\n\n- First\n- Second\n";
+        assert_eq!(codes(input), vec![]);
+    }
+
+    #[test]
+    fn test_lint_headline() {
+        let input = "Add a headline that is considerably longer than fifty columns.\nBody\n";
+        let diagnostics = lint(input, &Options::default());
+
+        assert_eq!(
+            diagnostics.iter().map(|d| d.code).collect::<Vec<_>>(),
+            vec!["headline-length", "headline-period", "headline-blank-line"]
+        );
+        assert_eq!(diagnostics[2].line_number, 2);
+        assert_eq!(diagnostics[2].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_lint_headline_not_prose() {
+        let long = format!("- {}", ["word"; 30].join(" "));
+        let input = format!("{long}\nBody directly below\n");
+        assert_eq!(
+            codes(&input),
+            vec![(1, "headline-length"), (2, "headline-blank-line")]
+        );
+
+        let input = "\n    indented subject.\n\nBody\n";
+        assert_eq!(codes(input), vec![(2, "headline-period")]);
+    }
+
+    #[test]
+    fn test_lint_trailing_whitespace() {
+        let input = "Subject\n\nBody line   \n\n    code  \n";
+        assert_eq!(
+            codes(input),
+            vec![(3, "trailing-whitespace"), (5, "trailing-whitespace")]
+        );
+    }
+
+    #[test]
+    fn test_lint_unwrappable_lines() {
+        let url = format!("https://example.com/{}", "a".repeat(80));
+        let long_prose = ["word"; 20].join(" ");
+        let input = format!("Subject\n\nSee {url}\n{long_prose}\n");
+
+        // The wrappable prose line is the formatter's job, not a lint
+        assert_eq!(codes(&input), vec![(3, "long-line")]);
+    }
+
+    #[test]
+    fn test_lint_mixed_bullets() {
+        let input = "Subject\n\nChanges:\n- First\n* Second\n  - Nested\n  - Nested again\n";
+        assert_eq!(codes(input), vec![(5, "mixed-bullets")]);
+    }
+
+    #[test]
+    fn test_lint_conventional() {
+        let input = "Fix the race\n\nBody\n";
+        assert_eq!(codes(input), vec![]);
+
+        let opts = Options {
+            conventional: true,
+            ..Options::default()
+        };
+        let diagnostics = lint(input, &opts);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "conventional-format");
+        assert_eq!(diagnostics[0].line_number, 1);
+    }
+
+    #[test]
+    fn test_lint_ignores_scissors_tail() {
+        let input = "Subject\n\nBody\n# ------------------------ >8 ------------------------\n+diff line   \n";
+        assert_eq!(codes(input), vec![]);
+    }
+}
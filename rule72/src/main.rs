@@ -1,12 +1,36 @@
 //! Command-line interface for rule72 commit message formatter.
 //!
-//! Reads commit messages from stdin and outputs formatted text to stdout,
-//! making it suitable for Git hooks, editor integration, and batch processing.
+//! Reads commit messages from stdin (or the given files) and outputs formatted
+//! text to stdout, making it suitable for Git hooks, editor integration, and
+//! batch processing. With `--in-place` each file is rewritten atomically.
+//! With `--check` nothing is rewritten; instead a diff is printed to stderr
+//! and the process exits with [`EXIT_CHECK_FAILED`] if reformatting is needed.
+//! The `check <rev-range>` subcommand does the same for every commit in a range,
+//! and `lint` reports style diagnostics as `FILE:LINE: severity[code]: message`.
+//! `--emit json` prints the parsed document instead of the reflowed text.
+//! `--verify-idempotent` reflows the output once more and fails with a diff
+//! if the second pass would change it.
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use clap::{Arg, Command};
-use rule72::{reflow, Options};
-use std::io::{self, Read};
+use rule72::check::check_named;
+use rule72::config::Config;
+use rule72::{check, git, lint, try_reflow, Options};
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process;
+use tempfile::NamedTempFile;
+
+/// Exit status when a message cannot be reflowed (PRD: input parse error).
+/// The message is copied through unchanged, or left untouched with `--in-place`.
+const EXIT_REFLOW_FAILED: i32 = 1;
+
+/// Exit status when `--check` finds a message that would be reformatted,
+/// `--verify-idempotent` finds unstable output, or `lint` reports diagnostics.
+/// Distinct from `1` (input error, see PRD) and `2` (clap usage error).
+const EXIT_CHECK_FAILED: i32 = 3;
 
 /// Main entry point for the rule72 CLI application.
 ///
@@ -16,20 +40,110 @@ fn main() -> Result<()> {
     let matches = Command::new("rule72")
         .version("0.2.2")
         .about("Git commit message formatter")
+        .args_conflicts_with_subcommands(true)
+        .arg(
+            Arg::new("files")
+                .value_name("FILE")
+                .help("Commit message files to format (default: stdin); put `--` before a FILE named `check` or `lint`")
+                .num_args(0..),
+        )
         .arg(
             Arg::new("width")
                 .short('w')
                 .long("width")
                 .value_name("N")
-                .help("Set body wrap width")
-                .default_value("72"),
+                .help("Set body wrap width [default: 72]")
+                .global(true),
         )
         .arg(
             Arg::new("headline-width")
                 .long("headline-width")
                 .value_name("N")
-                .help("Advisory headline width")
-                .default_value("50"),
+                .help("Advisory headline width [default: 50]")
+                .global(true),
+        )
+        .arg(
+            Arg::new("wrap")
+                .long("wrap")
+                .value_name("ALGORITHM")
+                .help("Line breaking algorithm [default: greedy]")
+                .value_parser(["greedy", "optimal"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("reflow")
+                .long("reflow")
+                .value_name("MODE")
+                .help("Rewrap whole over-width paragraphs (overflow), only the lines that need it (minimal), or all prose to fill the width (all) [default: overflow]")
+                .value_parser(["overflow", "minimal", "all"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("renumber-lists")
+                .long("renumber-lists")
+                .help("Renumber ordered list items sequentially, keeping each item's . or )")
+                .action(clap::ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("normalize-bullets")
+                .long("normalize-bullets")
+                .help("Use the first - or * bullet of each list for all of its items")
+                .action(clap::ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("footer-deny")
+                .long("footer-deny")
+                .value_name("TAG")
+                .help("Trailer tag never treated as a footer, e.g. EN (repeatable)")
+                .action(clap::ArgAction::Append)
+                .global(true),
+        )
+        .arg(
+            Arg::new("trailer-separators")
+                .long("trailer-separators")
+                .value_name("CHARS")
+                .help("Characters separating trailer tokens from values [default: git's trailer.separators, else :]")
+                .global(true),
+        )
+        .arg(
+            Arg::new("comment-char")
+                .long("comment-char")
+                .value_name("C")
+                .help("Git comment character [default: core.commentChar when run from a Git hook, else #]")
+                .global(true),
+        )
+        .arg(
+            Arg::new("footer-tag")
+                .long("footer-tag")
+                .value_name("TAG")
+                .help("Additional trailer tag to treat as a footer, e.g. Change-Id (repeatable)")
+                .action(clap::ArgAction::Append)
+                .global(true),
+        )
+        .arg(
+            Arg::new("conventional")
+                .long("conventional")
+                .help("Validate headlines as Conventional Commits when linting")
+                .action(clap::ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("conventional-type")
+                .long("conventional-type")
+                .value_name("TYPE")
+                .help("Allowed Conventional Commits type, e.g. feat (repeatable) [default: any]")
+                .action(clap::ArgAction::Append)
+                .global(true),
+        )
+        .arg(
+            Arg::new("conventional-scope")
+                .long("conventional-scope")
+                .value_name("SCOPE")
+                .help("Allowed Conventional Commits scope (repeatable) [default: any]")
+                .action(clap::ArgAction::Append)
+                .global(true),
         )
         .arg(
             Arg::new("debug-svg")
@@ -43,28 +157,342 @@ fn main() -> Result<()> {
                 .help("Output detailed trace of parsing pipeline")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .help("Do not rewrite; print a diff to stderr and exit non-zero if reformatting is needed")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("emit")
+                .long("emit")
+                .value_name("FORMAT")
+                .help("Output format: reflowed text, or the parsed document as JSON (one line per input)")
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .conflicts_with("check"),
+        )
+        .arg(
+            Arg::new("verify-idempotent")
+                .long("verify-idempotent")
+                .help("Reflow the output again; print a diff to stderr and exit non-zero if it changes")
+                .conflicts_with_all(["check", "emit"])
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("in-place")
+                .short('i')
+                .long("in-place")
+                .help("Rewrite the given files instead of printing to stdout")
+                .conflicts_with_all(["check", "emit"])
+                .action(clap::ArgAction::SetTrue),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Check every commit message in a revision range without rewriting")
+                .arg(
+                    Arg::new("range")
+                        .value_name("REV-RANGE")
+                        .help("Commits to check, e.g. origin/main..HEAD")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("lint")
+                .about("Report style diagnostics instead of rewriting")
+                .arg(
+                    Arg::new("files")
+                        .value_name("FILE")
+                        .help("Commit message files to lint (default: stdin)")
+                        .num_args(0..),
+                ),
+        )
         .get_matches();
 
-    let width: usize = matches.get_one::<String>("width").unwrap().parse()?;
-    let headline_width: usize = matches
-        .get_one::<String>("headline-width")
-        .unwrap()
-        .parse()?;
-    let debug_svg = matches.get_one::<String>("debug-svg").cloned();
-    let debug_trace = matches.get_flag("debug-trace");
-
-    let opts = Options {
-        width,
-        headline_width,
-        debug_svg,
-        debug_trace,
-    };
+    // Defaults < core.commentChar (in hooks) < .rule72.toml < rule72.* git config < CLI
+    let mut opts = Options::default();
+    if let Some(comment_char) = detect_comment_char() {
+        opts.comment_char = comment_char;
+    }
+    Config::discover(&env::current_dir()?)?.apply(&mut opts);
+
+    if let Some(width) = matches.get_one::<String>("width") {
+        opts.width = width.parse()?;
+    }
+    if let Some(headline_width) = matches.get_one::<String>("headline-width") {
+        opts.headline_width = headline_width.parse()?;
+    }
+    if let Some(wrap) = matches.get_one::<String>("wrap") {
+        opts.wrap = wrap.parse().map_err(anyhow::Error::msg)?;
+    }
+    if let Some(reflow) = matches.get_one::<String>("reflow") {
+        opts.reflow = reflow.parse().map_err(anyhow::Error::msg)?;
+    }
+    if matches.get_flag("renumber-lists") {
+        opts.renumber_lists = true;
+    }
+    if matches.get_flag("normalize-bullets") {
+        opts.normalize_bullets = true;
+    }
+    if let Some(value) = matches.get_one::<String>("comment-char") {
+        opts.comment_char = single_char(value)
+            .with_context(|| format!("--comment-char must be a single character, got {value:?}"))?;
+    }
+    if let Some(tags) = matches.get_many::<String>("footer-tag") {
+        opts.footer_tags = tags.cloned().collect();
+    }
+    if let Some(tags) = matches.get_many::<String>("footer-deny") {
+        opts.footer_deny = tags.cloned().collect();
+    }
+    if let Some(separators) = matches.get_one::<String>("trailer-separators") {
+        opts.trailer_separators = separators.clone();
+    }
+    if matches.get_flag("conventional") {
+        opts.conventional = true;
+    }
+    if let Some(types) = matches.get_many::<String>("conventional-type") {
+        opts.conventional_types = types.cloned().collect();
+    }
+    if let Some(scopes) = matches.get_many::<String>("conventional-scope") {
+        opts.conventional_scopes = scopes.cloned().collect();
+    }
+    opts.debug_svg = matches.get_one::<String>("debug-svg").cloned();
+    opts.debug_trace = matches.get_flag("debug-trace");
+
+    match matches.subcommand() {
+        Some(("check", sub)) => {
+            opts.validate()?;
+            let range = sub.get_one::<String>("range").unwrap();
+            return check_range(range, &opts);
+        }
+        Some(("lint", sub)) => {
+            let files: Vec<&String> = sub
+                .get_many::<String>("files")
+                .map(|files| files.collect())
+                .unwrap_or_default();
+            opts.validate()?;
+            return lint_files(&files, &opts);
+        }
+        _ => {}
+    }
+
+    let files: Vec<&String> = matches
+        .get_many::<String>("files")
+        .map(|files| files.collect())
+        .unwrap_or_default();
+    let check_mode = matches.get_flag("check");
+    let in_place = matches.get_flag("in-place");
+    let emit_json = matches.get_one::<String>("emit").map(String::as_str) == Some("json");
+    let verify_idempotent = matches.get_flag("verify-idempotent");
+    if check_mode || emit_json {
+        // Only plain reflowing can fall back to copying the input through
+        opts.validate()?;
+    }
+
+    if files.is_empty() {
+        if in_place {
+            bail!("--in-place requires at least one FILE");
+        }
+
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+
+        if check_mode {
+            if let Some(diff) = check(&input, &opts) {
+                eprint!("{diff}");
+                process::exit(EXIT_CHECK_FAILED);
+            }
+            return Ok(());
+        }
+        if emit_json {
+            println!("{}", document_json(&input, &opts)?);
+            return Ok(());
+        }
+
+        match try_reflow(&input, &opts) {
+            Ok(output) => {
+                print!("{output}");
+                if verify_idempotent && !is_idempotent(&output, &opts, "<stdin>") {
+                    process::exit(EXIT_CHECK_FAILED);
+                }
+            }
+            Err(err) => {
+                // Never lose the message: copy it through unchanged
+                print!("{input}");
+                eprintln!("rule72: {err}");
+                process::exit(EXIT_REFLOW_FAILED);
+            }
+        }
+        return Ok(());
+    }
+
+    let mut check_failed = false;
+    let mut reflow_failed = false;
+    for path in files {
+        let input = fs::read_to_string(path).with_context(|| format!("failed to read {path}"))?;
+
+        if check_mode {
+            if let Some(diff) = check_named(&input, &opts, path, path) {
+                eprint!("{diff}");
+                check_failed = true;
+            }
+        } else if emit_json {
+            println!("{}", document_json(&input, &opts)?);
+        } else {
+            match try_reflow(&input, &opts) {
+                Ok(output) => {
+                    if !in_place {
+                        print!("{output}");
+                    } else if output != input {
+                        write_in_place(Path::new(path), &output)
+                            .with_context(|| format!("failed to rewrite {path}"))?;
+                    }
+                    if verify_idempotent && !is_idempotent(&output, &opts, path) {
+                        check_failed = true;
+                    }
+                }
+                Err(err) => {
+                    // In place the file is left untouched; otherwise copy it through
+                    if !in_place {
+                        print!("{input}");
+                    }
+                    eprintln!("rule72: {path}: {err}");
+                    reflow_failed = true;
+                }
+            }
+        }
+    }
+
+    if reflow_failed {
+        process::exit(EXIT_REFLOW_FAILED);
+    }
+    if check_failed {
+        process::exit(EXIT_CHECK_FAILED);
+    }
+    Ok(())
+}
+
+/// Reflow `output` once more and report a diff to stderr if it changes.
+fn is_idempotent(output: &str, opts: &Options, name: &str) -> bool {
+    match check_named(output, opts, "reflowed once", "reflowed twice") {
+        Some(diff) => {
+            eprintln!("rule72: {name}: reflowing the output again changes it");
+            eprint!("{diff}");
+            false
+        }
+        None => true,
+    }
+}
+
+/// Parse `input` (up to the scissors line) and serialize the document as JSON.
+#[cfg(feature = "serde")]
+fn document_json(input: &str, opts: &Options) -> Result<String> {
+    let (message, _) = rule72::utils::split_at_scissors(input, opts.comment_char);
+    Ok(serde_json::to_string(&rule72::parse_document(
+        message, opts,
+    ))?)
+}
+
+#[cfg(not(feature = "serde"))]
+fn document_json(_input: &str, _opts: &Options) -> Result<String> {
+    bail!("--emit json requires rule72 to be built with the `serde` feature")
+}
+
+/// Atomically replace the file at `path` with `contents`.
+///
+/// Writes a temporary file next to the target, copies the original
+/// permissions, syncs it to disk and renames it over the original, so an
+/// interrupted run or a crash never leaves a truncated commit message behind. Symlinks are resolved first so
+/// that the link itself is preserved.
+fn write_in_place(path: &Path, contents: &str) -> Result<()> {
+    let target = fs::canonicalize(path)?;
+    let dir = target.parent().unwrap_or(Path::new("."));
+    let permissions = fs::metadata(&target)?.permissions();
+
+    let mut tmp = NamedTempFile::new_in(dir)?;
+    tmp.write_all(contents.as_bytes())?;
+    tmp.as_file().set_permissions(permissions)?;
+    tmp.as_file().sync_all()?;
+    tmp.persist(&target)?;
+    Ok(())
+}
+
+/// Return the only character of `value`, if it has exactly one.
+fn single_char(value: &str) -> Option<char> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !c.is_whitespace() => Some(c),
+        _ => None,
+    }
+}
+
+/// When run from a Git hook, follow the repository's `core.commentChar`.
+/// Git exports `GIT_DIR`/`GIT_INDEX_FILE` to hooks; `auto` and
+/// multi-character values fall back to the default.
+fn detect_comment_char() -> Option<char> {
+    if env::var_os("GIT_DIR").is_none() && env::var_os("GIT_INDEX_FILE").is_none() {
+        return None;
+    }
+    single_char(&git::config_get("core.commentChar")?)
+}
+
+/// Check every commit in `range`, printing the SHA, subject and diff of each
+/// non-conforming message. Exits with [`EXIT_CHECK_FAILED`] if any were found.
+fn check_range(range: &str, opts: &Options) -> Result<()> {
+    let commits = git::rev_list(range)?;
+    let mut failed = 0;
+
+    for sha in &commits {
+        let message = git::commit_message(sha)?;
+        if let Some(diff) = check(&message, opts) {
+            let subject = message.lines().next().unwrap_or_default();
+            println!("{sha} {subject}");
+            print!("{diff}");
+            failed += 1;
+        }
+    }
 
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
+    if failed > 0 {
+        eprintln!(
+            "{failed} of {} commit(s) in {range} need reformatting",
+            commits.len()
+        );
+        process::exit(EXIT_CHECK_FAILED);
+    }
+    Ok(())
+}
+
+/// Lint each file (or stdin), printing one `NAME:LINE: severity[code]: message`
+/// line per diagnostic. Exits with [`EXIT_CHECK_FAILED`] if any were reported.
+fn lint_files(files: &[&String], opts: &Options) -> Result<()> {
+    let inputs = if files.is_empty() {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+        vec![("<stdin>".to_string(), input)]
+    } else {
+        files
+            .iter()
+            .map(|path| {
+                let input =
+                    fs::read_to_string(path).with_context(|| format!("failed to read {path}"))?;
+                Ok((path.to_string(), input))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
 
-    let output = reflow(&input, &opts);
-    print!("{output}");
+    let mut found = false;
+    for (name, input) in &inputs {
+        for diagnostic in lint(input, opts) {
+            println!(
+                "{name}:{}: {}[{}]: {}",
+                diagnostic.line_number, diagnostic.severity, diagnostic.code, diagnostic.message
+            );
+            found = true;
+        }
+    }
 
+    if found {
+        process::exit(EXIT_CHECK_FAILED);
+    }
     Ok(())
 }
@@ -1,11 +1,25 @@
 //! Pretty printing: Format document chunks with appropriate wrapping and spacing.
 //!
 //! This module handles the final formatting stage, applying content-aware
-//! formatting rules to each chunk type (greedy wrap for prose, verbatim for
-//! code, proper indentation for lists, etc.).
+//! formatting rules to each chunk type (greedy or optimal wrap for prose, verbatim for
+//! code, proper indentation for lists, quote prefixes for quotations, etc.).
 
-use crate::types::{Category, ContChunk, Document, ListNode, Options};
-use crate::utils::{display_width, extract_bullet_prefix, wrap_text};
+use std::borrow::Cow;
+
+use crate::lexer::opens_block;
+use crate::types::{
+    CatLine, Category, ContChunk, Document, ListItem, ListNode, Options, ReflowMode,
+};
+use crate::utils::{
+    count_indent, display_width, extract_bullet_prefix, join_lines, looks_like_code,
+    ordered_marker, prefix_width, quote_depth, quote_prefix, wrap_text_guarded,
+};
+
+/// Wrap prose so that no wrapped line starts a new list item, footer,
+/// comment or other block when the output is formatted again.
+fn wrap_prose(text: &str, width: usize, opts: &Options) -> Vec<String> {
+    wrap_text_guarded(text, width, opts.wrap, |line| opens_block(line, opts))
+}
 
 /// Pretty print the document structure into formatted text
 pub fn pretty_print(doc: &Document, opts: &Options) -> String {
@@ -17,7 +31,7 @@ pub fn pretty_print(doc: &Document, opts: &Options) -> String {
     }
 
     // Print body chunks
-    for chunk in &doc.body_chunks {
+    for (idx, chunk) in doc.body_chunks.iter().enumerate() {
         match chunk {
             ContChunk::Code(lines) | ContChunk::Comment(lines) | ContChunk::Table(lines) => {
                 for line in lines {
@@ -28,32 +42,36 @@ pub fn pretty_print(doc: &Document, opts: &Options) -> String {
                 // Check if this is just an empty line
                 if lines.len() == 1 && lines[0].final_category == Category::Empty {
                     output.push(String::new());
-                } else {
-                    let needs_wrap = lines.iter().any(|l| display_width(&l.text) > opts.width);
-                    if needs_wrap {
-                        let text = lines
-                            .iter()
-                            .map(|l| l.text.trim())
-                            .collect::<Vec<_>>()
-                            .join(" ");
-                        let wrapped = wrap_text(&text, opts.width);
-                        output.extend(wrapped);
-                    } else {
-                        for line in lines {
-                            output.push(line.text.trim_end().to_string());
-                        }
+                } else if doc.headline.is_none() && lines[0].line_number == 0 {
+                    // A first line not taken as the headline (e.g. a URL) is
+                    // still the subject; rewrapping would make a new headline
+                    for line in lines {
+                        output.push(line.text.trim_end().to_string());
                     }
+                } else {
+                    let lines: Vec<String> = lines
+                        .iter()
+                        .map(|l| l.text.trim_end().to_string())
+                        .collect();
+                    let refill = refills(&doc.body_chunks, idx, opts);
+                    output.extend(pretty_print_paragraph(&lines, refill, 0, opts));
                 }
             }
+            ContChunk::Quote(lines) => {
+                output.extend(pretty_print_quote(lines, opts));
+            }
             ContChunk::List(list_node) => {
-                output.extend(pretty_print_list(list_node, opts, 0));
+                output.extend(pretty_print_list(list_node, opts));
             }
         }
     }
 
     // Print footers
     if !doc.footers.is_empty() {
-        output.push(String::new()); // Blank line before footers
+        // Blank line before footers, unless the body already ends with one
+        if output.last().is_some_and(|line| !line.is_empty()) {
+            output.push(String::new());
+        }
         for footer in &doc.footers {
             output.push(footer.text.trim_end().to_string());
         }
@@ -62,9 +80,163 @@ pub fn pretty_print(doc: &Document, opts: &Options) -> String {
     output.join("\n") + "\n"
 }
 
+/// Text of a quoted line after its quote prefix, if it is quoted prose that
+/// may be rewrapped: not blank, not indented code and not a quoted list
+/// item, fence or other block
+fn quoted_prose<'a>(line: &'a CatLine, opts: &Options) -> Option<&'a str> {
+    let content = line.text[quote_prefix(&line.text).len()..].trim_end();
+    let trimmed = content.trim_start();
+    if trimmed.is_empty() || count_indent(content) >= 4 || opens_block(trimmed, opts) {
+        None
+    } else {
+        Some(content)
+    }
+}
+
+/// Pretty print a quotation. Consecutive prose lines at the same nesting
+/// level are wrapped like a paragraph, re-emitting the quote prefix of the
+/// first line on every output line; everything else is kept verbatim.
+pub fn pretty_print_quote(lines: &[CatLine], opts: &Options) -> Vec<String> {
+    let mut output = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        if quoted_prose(&lines[i], opts).is_none() {
+            output.push(lines[i].text.trim_end().to_string());
+            i += 1;
+            continue;
+        }
+
+        let prefix = quote_prefix(&lines[i].text);
+        let depth = quote_depth(prefix);
+        let mut end = i + 1;
+        while end < lines.len()
+            && quote_depth(quote_prefix(&lines[end].text)) == depth
+            && quoted_prose(&lines[end], opts).is_some()
+        {
+            end += 1;
+        }
+        let run = &lines[i..end];
+
+        if opts.reflow == ReflowMode::Minimal {
+            let lines: Vec<(&str, &str)> = run
+                .iter()
+                .filter_map(|l| Some((quote_prefix(&l.text), quoted_prose(l, opts)?)))
+                .collect();
+            let hanging = lines.last().map_or(prefix, |(prefix, _)| prefix);
+            output.extend(rewrap_minimal(&lines, hanging, opts));
+        } else if opts.reflow == ReflowMode::All
+            || run.iter().any(|l| display_width(&l.text) > opts.width)
+        {
+            let prefix_width = prefix_width(prefix);
+            let text = join_lines(run.iter().filter_map(|l| quoted_prose(l, opts)));
+            for line in wrap_prose(&text, opts.width.saturating_sub(prefix_width), opts) {
+                output.push(format!("{prefix}{line}"));
+            }
+        } else {
+            for line in run {
+                output.push(line.text.trim_end().to_string());
+            }
+        }
+        i = end;
+    }
+
+    output
+}
+
+/// Bullet prefix of every item in `list`. With [`Options::renumber_lists`]
+/// ordered items are numbered sequentially from the first one, keeping each
+/// item's delimiter; with [`Options::normalize_bullets`] `-` and `*` items
+/// take the marker of the first such item. Indentation and the spacing after
+/// the marker are preserved.
+fn item_prefixes<'a>(list: &'a ListNode, opts: &Options) -> Vec<Cow<'a, str>> {
+    let mut next_number = None;
+    let mut first_bullet = None;
+
+    list.items
+        .iter()
+        .map(|item| {
+            let prefix = extract_bullet_prefix(&item.bullet_line.text);
+            // The bullet itself, without a task-list checkbox
+            let marker = prefix.split_whitespace().next().unwrap_or_default();
+            let lead = &prefix[..prefix.len() - prefix.trim_start().len()];
+            let trail = &prefix[lead.len() + marker.len()..];
+
+            let replacement = if let Some((number, delimiter)) = ordered_marker(marker) {
+                let number = next_number.unwrap_or(number);
+                next_number = number.checked_add(1);
+                opts.renumber_lists.then(|| format!("{number}{delimiter}"))
+            } else if matches!(marker, "-" | "*") {
+                let first = *first_bullet.get_or_insert(marker);
+                opts.normalize_bullets.then(|| first.to_string())
+            } else {
+                None
+            };
+
+            match replacement {
+                Some(new_marker) if new_marker != marker => {
+                    Cow::Owned(format!("{lead}{new_marker}{trail}"))
+                }
+                _ => Cow::Borrowed(prefix),
+            }
+        })
+        .collect()
+}
+
+/// Text of `line` moved so that column `from` lands on column `to`, if it is
+/// indented at least `from` columns, e.g. when the marker of the list item
+/// it belongs to grew from `9.` to `10.`
+fn realign(line: &CatLine, from: usize, to: usize) -> String {
+    if from != to && line.indent >= from {
+        let indent = line.indent + to - from;
+        format!("{}{}", " ".repeat(indent), line.text.trim())
+    } else {
+        line.text.trim_end().to_string()
+    }
+}
+
+/// Copy of `chunk` with every line moved like [`realign`], so that blocks
+/// nested under a list item stay lined up with its text
+fn realign_chunk(chunk: &ContChunk, from: usize, to: usize) -> ContChunk {
+    let line = |l: &CatLine| CatLine {
+        text: realign(l, from, to),
+        indent: if l.indent >= from {
+            l.indent + to - from
+        } else {
+            l.indent
+        },
+        ..l.clone()
+    };
+    let lines = |lines: &[CatLine]| lines.iter().map(line).collect();
+    match chunk {
+        ContChunk::Table(l) => ContChunk::Table(lines(l)),
+        ContChunk::Paragraph(l) => ContChunk::Paragraph(lines(l)),
+        ContChunk::Code(l) => ContChunk::Code(lines(l)),
+        ContChunk::Comment(l) => ContChunk::Comment(lines(l)),
+        // A quote indented four columns would read back as code
+        ContChunk::Quote(l) if l.iter().any(|l| line(l).indent >= 4) => ContChunk::Quote(l.clone()),
+        ContChunk::Quote(l) => ContChunk::Quote(lines(l)),
+        ContChunk::List(list) => ContChunk::List(ListNode {
+            introduction: lines(&list.introduction),
+            items: list
+                .items
+                .iter()
+                .map(|item| ListItem {
+                    bullet_line: line(&item.bullet_line),
+                    continuation: lines(&item.continuation),
+                    children: item
+                        .children
+                        .iter()
+                        .map(|child| realign_chunk(child, from, to))
+                        .collect(),
+                })
+                .collect(),
+        }),
+    }
+}
 
 /// Pretty print a list node with proper indentation and wrapping
-pub fn pretty_print_list(list: &ListNode, opts: &Options, _depth: usize) -> Vec<String> {
+pub fn pretty_print_list(list: &ListNode, opts: &Options) -> Vec<String> {
     let mut output = Vec::new();
 
     // Print introduction lines first
@@ -76,58 +248,233 @@ pub fn pretty_print_list(list: &ListNode, opts: &Options, _depth: usize) -> Vec<
         }
     }
 
-    for item in &list.items {
-        let bullet_prefix = extract_bullet_prefix(&item.bullet_line.text);
-        let bullet_width = display_width(bullet_prefix);
-        let text_start = item.bullet_line.text[bullet_prefix.len()..].trim_start();
+    for (item, bullet_prefix) in list.items.iter().zip(item_prefixes(list, opts)) {
+        let original_prefix = extract_bullet_prefix(&item.bullet_line.text);
+        let bullet_width = prefix_width(&bullet_prefix);
+        let original_width = prefix_width(original_prefix);
+        let text_start = item.bullet_line.text[original_prefix.len()..].trim_start();
+
+        // Lines aligned with the item text follow a marker that grew or
+        // shrank, e.g. from `9.` to `10.`
+        let continuation: Vec<String> = item
+            .continuation
+            .iter()
+            .map(|l| realign(l, original_width, bullet_width))
+            .collect();
 
         // Combine bullet line and continuation
-        let mut full_text = text_start.to_string();
-        for cont in &item.continuation {
-            full_text.push(' ');
-            full_text.push_str(cont.text.trim());
-        }
+        let full_text =
+            join_lines(std::iter::once(text_start).chain(continuation.iter().map(String::as_str)));
 
         // Check if wrapping is needed
         let first_line = format!("{bullet_prefix}{text_start}");
-        if display_width(&first_line) > opts.width
-            || item
-                .continuation
-                .iter()
-                .any(|l| display_width(&l.text) > opts.width)
-        {
-            let wrapped = wrap_text(&full_text, opts.width - bullet_width);
-            for (i, line) in wrapped.iter().enumerate() {
-                if i == 0 {
-                    output.push(format!("{bullet_prefix}{line}"));
-                } else {
-                    let padding = " ".repeat(bullet_width);
-                    output.push(format!("{padding}{line}"));
-                }
-            }
+        let fits = display_width(&first_line) <= opts.width
+            && continuation.iter().all(|l| display_width(l) <= opts.width);
+        let rewrap = match opts.reflow {
+            ReflowMode::Overflow => !fits,
+            ReflowMode::Minimal => false,
+            ReflowMode::All => !fits || !matches!(item.children.first(), Some(ContChunk::Code(_))),
+        };
+        let padding = " ".repeat(bullet_width);
+        let wrapped = rewrap
+            .then(|| wrap_paragraph(&full_text, &bullet_prefix, &padding, bullet_width, opts));
+        if opts.reflow == ReflowMode::Minimal {
+            let lines: Vec<(&str, &str)> = std::iter::once((bullet_prefix.as_ref(), text_start))
+                .chain(continuation.iter().map(|l| (leading(l), l.trim_start())))
+                .collect();
+            output.extend(rewrap_minimal(&lines, &padding, opts));
+        } else if let Some(wrapped) = wrapped {
+            output.extend(wrapped);
         } else {
             // Keep original formatting if within width
-            output.push(item.bullet_line.text.trim_end().to_string());
-            for cont in &item.continuation {
-                output.push(cont.text.trim_end().to_string());
+            output.push(first_line.trim_end().to_string());
+            output.extend(continuation);
+        }
+
+        // Further paragraphs, code and nested lists of the item
+        for (idx, child) in item.children.iter().enumerate() {
+            let realigned;
+            let child = if bullet_width == original_width {
+                child
+            } else {
+                realigned = realign_chunk(child, original_width, bullet_width);
+                &realigned
+            };
+            match child {
+                ContChunk::Paragraph(lines) if lines[0].final_category == Category::Empty => {
+                    output.push(String::new());
+                }
+                ContChunk::Paragraph(lines) => {
+                    let lines: Vec<String> = lines
+                        .iter()
+                        .map(|l| l.text.trim_end().to_string())
+                        .collect();
+                    let refill = refills(&item.children, idx, opts);
+                    output.extend(pretty_print_paragraph(&lines, refill, bullet_width, opts));
+                }
+                ContChunk::Quote(lines) => output.extend(pretty_print_quote(lines, opts)),
+                ContChunk::List(nested) => {
+                    output.extend(pretty_print_list(nested, opts));
+                }
+                ContChunk::Code(lines) | ContChunk::Table(lines) | ContChunk::Comment(lines) => {
+                    for line in lines {
+                        output.push(line.text.trim_end().to_string());
+                    }
+                }
             }
         }
+    }
+
+    output
+}
+
+/// Pretty print a paragraph. If any line is over the width, or always if
+/// `refill` is set, the paragraph is rewrapped keeping the indentation of
+/// its first line and, on the following lines, the hanging indentation of
+/// its second line. Every line is wrapped at the width minus the deeper of
+/// the two, so all lines fit. `content_indent` is the column of the text
+/// of the list item the paragraph belongs to, or 0 outside lists.
+fn pretty_print_paragraph(
+    lines: &[String],
+    refill: bool,
+    content_indent: usize,
+    opts: &Options,
+) -> Vec<String> {
+    let fits = lines.iter().all(|l| display_width(l) <= opts.width);
+    if fits && !refill {
+        return lines.to_vec();
+    }
+    if opts.reflow == ReflowMode::Minimal {
+        let minimal: Vec<(&str, &str)> =
+            lines.iter().map(|l| (leading(l), l.trim_start())).collect();
+        return rewrap_minimal(&minimal, leading(&lines[lines.len() - 1]), opts);
+    }
+
+    let first = leading(&lines[0]);
+    let hanging = lines.get(1).map_or(first, |line| leading(line));
+    let text = join_lines(lines.iter().map(String::as_str));
+    wrap_paragraph(&text, first, hanging, content_indent, opts)
+}
+
+/// Wrap the text of a paragraph behind its `first` and `hanging`
+/// indentation (or list marker), at the width minus the deeper of the two.
+/// A wrapped line that reads as code, being dense with symbols or indented
+/// four columns past `content_indent`, is a code block of its own when the
+/// output is formatted again, and the text around it forms new paragraphs.
+/// So the text before and after such a line is wrapped on its own, the way
+/// the next run would wrap it.
+fn wrap_paragraph(
+    text: &str,
+    first: &str,
+    hanging: &str,
+    content_indent: usize,
+    opts: &Options,
+) -> Vec<String> {
+    let indent = count_indent(first).max(count_indent(hanging));
+    let wrapped = wrap_prose(text, opts.width.saturating_sub(indent), opts);
+    // A list marker keeps its line a list item, whatever the text
+    let reads_as_code = |padding: &str, line: &str| {
+        padding.trim().is_empty()
+            && (count_indent(padding) >= content_indent + 4 || looks_like_code(line.trim()))
+    };
+    let code = wrapped
+        .iter()
+        .enumerate()
+        .position(|(i, line)| reads_as_code(if i == 0 { first } else { hanging }, line));
+    let Some(code) = code else {
+        return wrapped
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let padding = if i == 0 { first } else { hanging };
+                format!("{padding}{line}")
+            })
+            .collect();
+    };
+
+    let mut output = Vec::new();
+    if code > 0 {
+        let before = join_lines(wrapped[..code].iter().map(String::as_str));
+        output.extend(wrap_paragraph(
+            &before,
+            first,
+            hanging,
+            content_indent,
+            opts,
+        ));
+    }
+    let padding = if code == 0 { first } else { hanging };
+    output.push(format!("{padding}{}", wrapped[code]));
+    if code + 1 < wrapped.len() {
+        let after = join_lines(wrapped[code + 1..].iter().map(String::as_str));
+        output.extend(wrap_paragraph(
+            &after,
+            hanging,
+            hanging,
+            content_indent,
+            opts,
+        ));
+    }
+    output
+}
+
+/// Whether the paragraph `chunks[idx]` is rewrapped even if it fits, as with
+/// [`ReflowMode::All`]. Prose directly next to code, without a blank line,
+/// is left alone: it may be part of a paragraph that an earlier run wrapped
+/// into a line read back as code, and rewrapping the part on its own would
+/// break it differently.
+fn refills(chunks: &[ContChunk], idx: usize, opts: &Options) -> bool {
+    let is_code = |chunk: Option<&ContChunk>| matches!(chunk, Some(ContChunk::Code(_)));
+    opts.reflow == ReflowMode::All
+        && !is_code(idx.checked_sub(1).and_then(|prev| chunks.get(prev)))
+        && !is_code(chunks.get(idx + 1))
+}
 
-        // Handle nested list
-        if let Some(nested) = &item.nested {
-            output.extend(pretty_print_list(nested, opts, _depth + 1));
+/// Rewrap only the lines that need it ([`ReflowMode::Minimal`]). Of each
+/// `(prefix, text)` line over the width, the words that fit stay and the
+/// rest flows into the start of the next line; once a line fits again the
+/// following lines are kept as they were. Text left over after the last
+/// line continues on new lines behind `hanging`.
+fn rewrap_minimal(lines: &[(&str, &str)], hanging: &str, opts: &Options) -> Vec<String> {
+    let mut output = Vec::new();
+    let mut carry = String::new();
+
+    for &(prefix, text) in lines {
+        let line = format!("{prefix}{}", text.trim_end());
+        if carry.is_empty() && display_width(&line) <= opts.width {
+            output.push(line);
+            continue;
         }
+
+        let text = join_lines([carry.as_str(), text]);
+        let available = opts.width.saturating_sub(prefix_width(prefix));
+        let wrapped = wrap_prose(&text, available, opts);
+        output.push(format!("{prefix}{}", wrapped[0]));
+        carry = join_lines(wrapped[1..].iter().map(String::as_str));
     }
 
+    if !carry.is_empty() {
+        let available = opts.width.saturating_sub(prefix_width(hanging));
+        for line in wrap_prose(&carry, available, opts) {
+            output.push(format!("{hanging}{line}"));
+        }
+    }
     output
 }
 
+/// Leading whitespace of a line
+fn leading(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::classifier::classify_with_context;
     use crate::lexer::lex_lines;
     use crate::tree_builder::build_document;
+    use crate::types::WrapAlgorithm;
+    use crate::utils::wrap_text;
 
     #[test]
     fn test_wrap_simple() {
@@ -154,6 +501,7 @@ mod tests {
             headline_width: 50,
             debug_svg: None,
             debug_trace: false,
+            ..Options::default()
         };
 
         let lexed = lex_lines(&lines, &opts);
@@ -165,6 +513,54 @@ mod tests {
         assert!(output.contains("- A very long list item"));
     }
 
+    #[test]
+    fn test_pretty_print_optimal_wrap() {
+        let lines = vec!["Subject", "", "aaa bb cc ddddd", "", "- aaa bb cc ddddd"];
+
+        let opts = Options {
+            width: 8,
+            wrap: WrapAlgorithm::Optimal,
+            ..Options::default()
+        };
+        let lexed = lex_lines(&lines, &opts);
+        let classified = classify_with_context(lexed);
+        let document = build_document(classified);
+        let output = pretty_print(&document, &opts);
+
+        assert_eq!(
+            output,
+            "Subject\n\naaa bb\ncc ddddd\n\n- aaa\n  bb cc\n  ddddd\n"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_japanese_paragraph() {
+        let lines = vec![
+            "Subject",
+            "",
+            "設定ファイルの読み込み処理を修正しました。以前は空の値が",
+            "無視されていました。",
+        ];
+
+        let opts = Options {
+            width: 30,
+            ..Options::default()
+        };
+        let lexed = lex_lines(&lines, &opts);
+        let classified = classify_with_context(lexed);
+        let document = build_document(classified);
+        let output = pretty_print(&document, &opts);
+
+        let body: Vec<&str> = output.lines().skip(2).collect();
+        assert!(body.len() > 1);
+        assert!(body.iter().all(|line| display_width(line) <= 30));
+        // Rejoined without spurious spaces between the original lines
+        assert_eq!(
+            body.concat(),
+            "設定ファイルの読み込み処理を修正しました。以前は空の値が無視されていました。"
+        );
+    }
+
     #[test]
     fn test_pretty_print_code_blocks() {
         let lines = vec![
@@ -188,6 +584,316 @@ mod tests {
         assert!(output.contains("    }"));
     }
 
+    #[test]
+    fn test_pretty_print_quotes() {
+        let lines = vec![
+            "Subject",
+            "",
+            "> the reviewer asked for this to be split into two commits",
+            ">",
+            ">> an older reply that is also far too long for the width",
+            "> - quoted list item",
+            "",
+            "Done.",
+        ];
+
+        let opts = Options {
+            width: 30,
+            ..Options::default()
+        };
+        let lexed = lex_lines(&lines, &opts);
+        let classified = classify_with_context(lexed);
+        let document = build_document(classified);
+        let output = pretty_print(&document, &opts);
+
+        assert_eq!(
+            output,
+            "Subject\n\n\
+             > the reviewer asked for this\n\
+             > to be split into two commits\n\
+             >\n\
+             >> an older reply that is also\n\
+             >> far too long for the width\n\
+             > - quoted list item\n\
+             \n\
+             Done.\n"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_subject_not_prose() {
+        // Rewrapping would turn the first wrapped line into a new headline
+        let input = "https://example.com/issues/1234 fix the scheduler race\n\nBody\n";
+        let lines: Vec<&str> = input.lines().collect();
+        let opts = Options {
+            width: 40,
+            ..Options::default()
+        };
+        let document = build_document(classify_with_context(lex_lines(&lines, &opts)));
+        assert!(document.headline.is_none());
+        assert_eq!(pretty_print(&document, &opts), input);
+    }
+
+    #[test]
+    fn test_pretty_print_comparison_is_not_a_quote() {
+        let lines = vec![
+            "Subject",
+            "",
+            ">=3.0 of libfoo is now required because the old allocator API was removed upstream",
+            "",
+            ">>= shifts the value right by the given number of bits",
+            "",
+            "> >= 2 reviewers asked for this to be split",
+        ];
+
+        let opts = Options {
+            width: 40,
+            ..Options::default()
+        };
+        let lexed = lex_lines(&lines, &opts);
+        let classified = classify_with_context(lexed);
+        let document = build_document(classified);
+        let output = pretty_print(&document, &opts);
+
+        assert_eq!(
+            output,
+            "Subject\n\n\
+             >=3.0 of libfoo is now required because\n\
+             the old allocator API was removed\n\
+             upstream\n\
+             \n\
+             >>= shifts the value right by the given\n\
+             number of bits\n\
+             \n\
+             > >= 2 reviewers asked for this to be\n\
+             > split\n"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_renumber_lists() {
+        let input = "Subject\n\n3) first\n3) second\n- aside\n1) third\n5. fourth\n6. fifth\n7. sixth\n8. seventh\n1. eighth\n   continued under the text\n  - nested\n  - nested again\n";
+        let lines: Vec<&str> = input.lines().collect();
+        let renumber = Options {
+            renumber_lists: true,
+            ..Options::default()
+        };
+        let document = build_document(classify_with_context(lex_lines(&lines, &renumber)));
+
+        // Off by default
+        assert_eq!(pretty_print(&document, &Options::default()), input);
+
+        assert_eq!(
+            pretty_print(&document, &renumber),
+            "Subject\n\n3) first\n4) second\n- aside\n5) third\n6. fourth\n7. fifth\n8. sixth\n9. seventh\n10. eighth\n    continued under the text\n  - nested\n  - nested again\n"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_normalize_bullets() {
+        let lines = vec![
+            "Subject",
+            "",
+            "* star",
+            "- dash",
+            "  - nested dash",
+            "  * nested star",
+            "🔥 emoji",
+        ];
+        let opts = Options {
+            normalize_bullets: true,
+            ..Options::default()
+        };
+        let document = build_document(classify_with_context(lex_lines(&lines, &opts)));
+
+        assert_eq!(
+            pretty_print(&document, &opts),
+            "Subject\n\n* star\n* dash\n  - nested dash\n  - nested star\n🔥 emoji\n"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_task_list() {
+        let lines = vec![
+            "Subject",
+            "",
+            "- [ ] port the scheduler to the new queue implementation",
+            "- [x] drop the legacy fallback path",
+        ];
+        let opts = Options {
+            width: 30,
+            ..Options::default()
+        };
+        let document = build_document(classify_with_context(lex_lines(&lines, &opts)));
+
+        // Continuations align with the text after the checkbox
+        assert_eq!(
+            pretty_print(&document, &opts),
+            "Subject\n\n- [ ] port the scheduler to\n      the new queue\n      implementation\n- [x] drop the legacy fallback\n      path\n"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_indented_paragraph() {
+        let lines = vec![
+            "Subject",
+            "",
+            "Breaking Changes:",
+            "",
+            "  The configuration file moved to the repository root and old paths are ignored.",
+            "",
+            "The migration script handles every known layout,",
+            "  including the nested one that predates the workspace split.",
+        ];
+        let opts = Options {
+            width: 40,
+            ..Options::default()
+        };
+        let document = build_document(classify_with_context(lex_lines(&lines, &opts)));
+
+        assert_eq!(
+            pretty_print(&document, &opts),
+            "Subject\n\n\
+             Breaking Changes:\n\
+             \n\
+             \x20 The configuration file moved to the\n\
+             \x20 repository root and old paths are\n\
+             \x20 ignored.\n\
+             \n\
+             The migration script handles every\n\
+             \x20 known layout, including the nested one\n\
+             \x20 that predates the workspace split.\n"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_list_item_paragraphs() {
+        let lines = vec![
+            "Subject",
+            "",
+            "1. Move the cache behind a trait.",
+            "",
+            "   The second paragraph explains why the old design did not scale.",
+            "",
+            "       cache.get(key)",
+            "   - a nested item whose follow-up is long",
+            "",
+            "     and wraps under the nested item text.",
+            "",
+            "Done.",
+        ];
+        let opts = Options {
+            width: 36,
+            ..Options::default()
+        };
+        let document = build_document(classify_with_context(lex_lines(&lines, &opts)));
+
+        assert_eq!(
+            pretty_print(&document, &opts),
+            "Subject\n\n\
+             1. Move the cache behind a trait.\n\
+             \n\
+             \x20  The second paragraph explains why\n\
+             \x20  the old design did not scale.\n\
+             \n\
+             \x20      cache.get(key)\n\
+             \x20  - a nested item whose follow-up\n\
+             \x20    is long\n\
+             \n\
+             \x20    and wraps under the nested item\n\
+             \x20    text.\n\
+             \n\
+             Done.\n"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_reflow_minimal() {
+        let input = "Subject\n\nA hand-broken paragraph whose first line was edited and is now too long.\nThe second line was\nbroken by hand.\nAnd the third stays.\n\n- an item whose first line grew past the width after an edit\n  continued here\n  and here.\n\n> quoted text that is too long for the width of forty\n> short\n";
+        let lines: Vec<&str> = input.lines().collect();
+        let opts = Options {
+            width: 40,
+            reflow: ReflowMode::Minimal,
+            ..Options::default()
+        };
+        let document = build_document(classify_with_context(lex_lines(&lines, &opts)));
+
+        // Lines after the first one that fits again are untouched
+        assert_eq!(
+            pretty_print(&document, &opts),
+            "Subject\n\n\
+             A hand-broken paragraph whose first line\n\
+             was edited and is now too long. The\n\
+             second line was broken by hand.\n\
+             And the third stays.\n\
+             \n\
+             - an item whose first line grew past the\n\
+             \x20 width after an edit continued here\n\
+             \x20 and here.\n\
+             \n\
+             > quoted text that is too long for the\n\
+             > width of forty short\n"
+        );
+
+        // A marker at the end of the line is not carried to the start of the
+        // next one, where it would open a list item
+        let input = "Subject\n\nThe scheduler drains the queue, see -\nstep two\n";
+        let lines: Vec<&str> = input.lines().collect();
+        let opts = Options { width: 36, ..opts };
+        let document = build_document(classify_with_context(lex_lines(&lines, &opts)));
+        assert_eq!(
+            pretty_print(&document, &opts),
+            "Subject\n\nThe scheduler drains the queue,\nsee - step two\n"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_reflow_all() {
+        let input = "Subject\n\nWritten with\nshort lines\nin an editor.\n\n- an item broken\n  by hand\n\n> a quoted\n> reply\n\n    code stays\n    as it is\n\n| a | b |\n\nSigned-off-by: A <a@example.com>\n";
+        let lines: Vec<&str> = input.lines().collect();
+        let opts = Options {
+            reflow: ReflowMode::All,
+            ..Options::default()
+        };
+        let document = build_document(classify_with_context(lex_lines(&lines, &opts)));
+
+        assert_eq!(
+            pretty_print(&document, &opts),
+            "Subject\n\n\
+             Written with short lines in an editor.\n\
+             \n\
+             - an item broken by hand\n\
+             \n\
+             > a quoted reply\n\
+             \n\
+             \x20   code stays\n\
+             \x20   as it is\n\
+             \n\
+             | a | b |\n\
+             \n\
+             Signed-off-by: A <a@example.com>\n"
+        );
+
+        // The second line reads as code once refilled, so the next run takes
+        // it as a code block; the text after it is wrapped on its own
+        let input = "Subject\n\nMove the helpers\nout of the old (a/b.rs, c/d.rs,\ne/f.rs) modules and drop\nthe shims.\n";
+        let lines: Vec<&str> = input.lines().collect();
+        let opts = Options { width: 32, ..opts };
+        let document = build_document(classify_with_context(lex_lines(&lines, &opts)));
+        let output = pretty_print(&document, &opts);
+        assert_eq!(
+            output,
+            "Subject\n\n\
+             Move the helpers out of the old\n\
+             (a/b.rs, c/d.rs, e/f.rs) modules\n\
+             and drop the shims.\n"
+        );
+        let lines: Vec<&str> = output.lines().collect();
+        let document = build_document(classify_with_context(lex_lines(&lines, &opts)));
+        assert!(matches!(document.body_chunks[2], ContChunk::Code(_)));
+        assert_eq!(pretty_print(&document, &opts), output);
+    }
+
     #[test]
     fn test_pretty_print_tables() {
         let lines = vec![
@@ -224,7 +930,7 @@ mod tests {
         let document = build_document(classified);
         let output = pretty_print(&document, &opts);
 
-        // Comments should be preserved as-is
+        // Comments and text that fits should be preserved as-is
         assert!(output.contains("# This is a comment"));
         assert!(output.contains("// Another comment"));
     }
@@ -258,6 +964,29 @@ mod tests {
             .unwrap();
         assert!(signed_off_idx > 0);
         assert_eq!(lines[signed_off_idx - 1], "");
+        assert_ne!(lines[signed_off_idx - 2], "");
+    }
+
+    #[test]
+    fn test_pretty_print_single_trailer() {
+        let lines = vec![
+            "Subject line",
+            "",
+            "Body text.",
+            "",
+            "Co-Authored-By: X <x@y>",
+        ];
+
+        let opts = Options::default();
+        let lexed = lex_lines(&lines, &opts);
+        let classified = classify_with_context(lexed);
+        let document = build_document(classified);
+        let output = pretty_print(&document, &opts);
+
+        assert_eq!(
+            output,
+            "Subject line\n\nBody text.\n\nCo-Authored-By: X <x@y>\n"
+        );
     }
 
     #[test]
@@ -292,6 +1021,7 @@ mod tests {
             headline_width: 50,
             debug_svg: None,
             debug_trace: false,
+            ..Options::default()
         };
 
         let lexed = lex_lines(&lines, &opts);
@@ -316,6 +1046,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_pretty_print_wrap_does_not_open_blocks() {
+        let opts = Options {
+            width: 30,
+            ..Options::default()
+        };
+        let input = "Subject line\n\nThe race was first reported in #123 and then the follow-up fix landed in Fixes: abc123 - or so\n\n- keep the trailers that the old hook dropped, like Signed-off-by:\n";
+        let lines: Vec<&str> = input.lines().collect();
+        let document = build_document(classify_with_context(lex_lines(&lines, &opts)));
+        let output = pretty_print(&document, &opts);
+
+        for line in output.lines().skip(1) {
+            assert!(!line.trim_start().starts_with('#'), "{line:?}");
+            assert!(!line.trim_start().starts_with("Fixes:"), "{line:?}");
+            assert!(!line.trim_start().starts_with("Signed-off-by:"), "{line:?}");
+            assert!(!line.starts_with("- or"), "{line:?}");
+        }
+    }
+
+    #[test]
+    fn test_pretty_print_tab_indented_bullet() {
+        let opts = Options {
+            width: 30,
+            ..Options::default()
+        };
+        let lines = vec![
+            "Subject line",
+            "",
+            "\t- the tab indented bullet drains the queue before it returns",
+        ];
+        let document = build_document(classify_with_context(lex_lines(&lines, &opts)));
+        let output = pretty_print(&document, &opts);
+
+        // Continuations line up after the bullet with the tab counted as four columns
+        assert_eq!(
+            output,
+            "Subject line\n\n\t- the tab indented bullet\n      drains the queue before\n      it returns\n"
+        );
+    }
+
     #[test]
     fn test_pretty_print_mixed_content() {
         let lines = vec![
@@ -362,4 +1132,18 @@ mod tests {
         // Should just be a newline
         assert_eq!(output, "\n");
     }
+
+    #[test]
+    fn test_pretty_print_fenced_prose_verbatim() {
+        let long = "This sentence inside a fence is far longer than the configured width and must not be rewrapped";
+        let lines = vec!["Subject line", "", "```text", long, "```"];
+
+        let opts = Options::default();
+        let lexed = lex_lines(&lines, &opts);
+        let classified = classify_with_context(lexed);
+        let document = build_document(classified);
+        let output = pretty_print(&document, &opts);
+
+        assert!(output.contains(&format!("```text\n{long}\n```")));
+    }
 }
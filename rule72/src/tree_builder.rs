@@ -5,6 +5,9 @@
 //! blocks, etc.), and footers.
 
 use crate::types::{CatLine, Category, ContChunk, Document, ListItem, ListNode};
+use crate::utils::{
+    extract_bullet_prefix, fence_marker, is_closing_fence, looks_like_code, prefix_width,
+};
 
 /// Build hierarchical document structure from classified lines
 pub fn build_document(lines: Vec<CatLine>) -> Document {
@@ -14,134 +17,136 @@ pub fn build_document(lines: Vec<CatLine>) -> Document {
         footers: Vec::new(),
     };
 
+    // Only the final contiguous block of trailers counts as footers; trailer-like
+    // lines earlier in the body are ordinary content.
+    let footer_start = trailing_footer_start(&lines);
+    document.footers = lines[footer_start..].to_vec();
+
     let mut current_chunk: Option<ContChunk> = None;
     let mut i = 0;
 
-    while i < lines.len() {
+    while i < footer_start {
         let line = &lines[i];
 
+        // Handle first line as potential headline
+        if i == 0 && line.final_category == Category::ProseGeneral {
+            document.headline = Some(line.clone());
+            i += 1;
+            continue;
+        }
+
         match line.final_category {
-            Category::Footer => {
-                // Finish current chunk and add footers
+            Category::Empty => {
+                // Finish current chunk
                 if let Some(chunk) = current_chunk.take() {
                     document.body_chunks.push(chunk);
                 }
-                // Collect all remaining lines as footers
-                for footer_line in &lines[i..] {
-                    document.footers.push(footer_line.clone());
-                }
-                break;
+                // Add empty line as a paragraph chunk
+                document
+                    .body_chunks
+                    .push(ContChunk::Paragraph(vec![line.clone()]));
+                i += 1;
             }
-            _ => {
-                // Handle first line as potential headline
-                if i == 0 && line.final_category == Category::ProseGeneral {
-                    document.headline = Some(line.clone());
-                    i += 1;
-                    continue;
+            Category::List => {
+                // Check if we can merge the last paragraph chunk as introduction to this list
+                let mut list_introduction = Vec::new();
+
+                // Check if the last chunk is a single-line paragraph ending with ":"
+                if let Some(ContChunk::Paragraph(para_lines)) = document.body_chunks.last() {
+                    if para_lines.len() == 1
+                        && para_lines[0].text.trim().ends_with(':')
+                        && (para_lines[0].final_category == Category::ProseGeneral
+                            || para_lines[0].final_category == Category::ProseIntroduction)
+                    {
+                        // Remove the last paragraph chunk and use it as introduction
+                        if let Some(ContChunk::Paragraph(intro_lines)) = document.body_chunks.pop()
+                        {
+                            list_introduction.extend(intro_lines);
+                        }
+                    }
                 }
 
-                match line.final_category {
-                    Category::Empty => {
-                        // Finish current chunk
+                // Finish current chunk if any
+                if let Some(chunk) = current_chunk.take() {
+                    document.body_chunks.push(chunk);
+                }
+
+                // Parse list but with our pre-determined introduction
+                let (mut list_node, consumed) = parse_list_simple(&lines[..footer_start], i);
+                list_node.introduction = list_introduction;
+                document.body_chunks.push(ContChunk::List(list_node));
+                i += consumed;
+            }
+            Category::Code => {
+                match &mut current_chunk {
+                    Some(ContChunk::Code(ref mut code_lines)) => {
+                        code_lines.push(line.clone());
+                    }
+                    _ => {
                         if let Some(chunk) = current_chunk.take() {
                             document.body_chunks.push(chunk);
                         }
-                        // Add empty line as a paragraph chunk
-                        document
-                            .body_chunks
-                            .push(ContChunk::Paragraph(vec![line.clone()]));
-                        i += 1;
+                        current_chunk = Some(ContChunk::Code(vec![line.clone()]));
                     }
-                    Category::List => {
-                        // Check if we can merge the last paragraph chunk as introduction to this list
-                        let mut list_introduction = Vec::new();
-
-                        // Check if the last chunk is a single-line paragraph ending with ":"
-                        if let Some(ContChunk::Paragraph(para_lines)) = document.body_chunks.last()
-                        {
-                            if para_lines.len() == 1
-                                && para_lines[0].text.trim().ends_with(':')
-                                && (para_lines[0].final_category == Category::ProseGeneral
-                                    || para_lines[0].final_category == Category::ProseIntroduction)
-                            {
-                                // Remove the last paragraph chunk and use it as introduction
-                                if let Some(ContChunk::Paragraph(intro_lines)) =
-                                    document.body_chunks.pop()
-                                {
-                                    list_introduction.extend(intro_lines);
-                                }
-                            }
-                        }
-
-                        // Finish current chunk if any
+                }
+                i += 1;
+            }
+            Category::Table => {
+                match &mut current_chunk {
+                    Some(ContChunk::Table(ref mut table_lines)) => {
+                        table_lines.push(line.clone());
+                    }
+                    _ => {
                         if let Some(chunk) = current_chunk.take() {
                             document.body_chunks.push(chunk);
                         }
-
-                        // Parse list but with our pre-determined introduction
-                        let (mut list_node, consumed) = parse_list_simple(&lines, i);
-                        list_node.introduction = list_introduction;
-                        document.body_chunks.push(ContChunk::List(list_node));
-                        i += consumed;
+                        current_chunk = Some(ContChunk::Table(vec![line.clone()]));
                     }
-                    Category::Code => {
-                        match &mut current_chunk {
-                            Some(ContChunk::Code(ref mut code_lines)) => {
-                                code_lines.push(line.clone());
-                            }
-                            _ => {
-                                if let Some(chunk) = current_chunk.take() {
-                                    document.body_chunks.push(chunk);
-                                }
-                                current_chunk = Some(ContChunk::Code(vec![line.clone()]));
-                            }
-                        }
-                        i += 1;
+                }
+                i += 1;
+            }
+            Category::Quote => {
+                match &mut current_chunk {
+                    Some(ContChunk::Quote(ref mut quote_lines)) => {
+                        quote_lines.push(line.clone());
                     }
-                    Category::Table => {
-                        match &mut current_chunk {
-                            Some(ContChunk::Table(ref mut table_lines)) => {
-                                table_lines.push(line.clone());
-                            }
-                            _ => {
-                                if let Some(chunk) = current_chunk.take() {
-                                    document.body_chunks.push(chunk);
-                                }
-                                current_chunk = Some(ContChunk::Table(vec![line.clone()]));
-                            }
+                    _ => {
+                        if let Some(chunk) = current_chunk.take() {
+                            document.body_chunks.push(chunk);
                         }
-                        i += 1;
+                        current_chunk = Some(ContChunk::Quote(vec![line.clone()]));
+                    }
+                }
+                i += 1;
+            }
+            Category::Comment => {
+                match &mut current_chunk {
+                    Some(ContChunk::Comment(ref mut comment_lines)) => {
+                        comment_lines.push(line.clone());
                     }
-                    Category::Comment => {
-                        match &mut current_chunk {
-                            Some(ContChunk::Comment(ref mut comment_lines)) => {
-                                comment_lines.push(line.clone());
-                            }
-                            _ => {
-                                if let Some(chunk) = current_chunk.take() {
-                                    document.body_chunks.push(chunk);
-                                }
-                                current_chunk = Some(ContChunk::Comment(vec![line.clone()]));
-                            }
+                    _ => {
+                        if let Some(chunk) = current_chunk.take() {
+                            document.body_chunks.push(chunk);
                         }
-                        i += 1;
+                        current_chunk = Some(ContChunk::Comment(vec![line.clone()]));
+                    }
+                }
+                i += 1;
+            }
+            _ => {
+                // ProseGeneral, ProseIntroduction, URL, non-trailing Footer -> paragraph
+                match &mut current_chunk {
+                    Some(ContChunk::Paragraph(ref mut para_lines)) => {
+                        para_lines.push(line.clone());
                     }
                     _ => {
-                        // ProseGeneral, ProseIntroduction, URL -> paragraph
-                        match &mut current_chunk {
-                            Some(ContChunk::Paragraph(ref mut para_lines)) => {
-                                para_lines.push(line.clone());
-                            }
-                            _ => {
-                                if let Some(chunk) = current_chunk.take() {
-                                    document.body_chunks.push(chunk);
-                                }
-                                current_chunk = Some(ContChunk::Paragraph(vec![line.clone()]));
-                            }
+                        if let Some(chunk) = current_chunk.take() {
+                            document.body_chunks.push(chunk);
                         }
-                        i += 1;
+                        current_chunk = Some(ContChunk::Paragraph(vec![line.clone()]));
                     }
                 }
+                i += 1;
             }
         }
     }
@@ -154,56 +159,66 @@ pub fn build_document(lines: Vec<CatLine>) -> Document {
     document
 }
 
+/// Index of the first line of the trailing footer block, or `lines.len()` if
+/// there is none. Trailing empty and comment lines belong to the block.
+fn trailing_footer_start(lines: &[CatLine]) -> usize {
+    let mut end = lines.len();
+    while end > 0
+        && matches!(
+            lines[end - 1].final_category,
+            Category::Empty | Category::Comment
+        )
+    {
+        end -= 1;
+    }
+
+    let mut start = end;
+    while start > 0 && lines[start - 1].final_category == Category::Footer {
+        start -= 1;
+    }
 
-/// Parse a list without looking for introduction lines
+    if start == end {
+        lines.len()
+    } else {
+        start
+    }
+}
+
+/// Parse a list without looking for introduction lines. The list ends at
+/// the first line that is not a list item or indented under one, or at an
+/// item indented less than the first one, which belongs to an outer list.
 fn parse_list_simple(lines: &[CatLine], start: usize) -> (ListNode, usize) {
     let mut items = Vec::new();
+    let list_indent = lines[start].indent;
     let mut i = start;
 
-    while i < lines.len() && lines[i].final_category == Category::List {
+    while i < lines.len()
+        && lines[i].final_category == Category::List
+        && lines[i].indent >= list_indent
+    {
         let bullet_line = lines[i].clone();
         i += 1;
 
-        // Collect continuation lines
+        // Collect continuation lines (indented relative to bullet) of the
+        // first paragraph
+        let content_indent = prefix_width(extract_bullet_prefix(&bullet_line.text));
         let mut continuation = Vec::new();
-        while i < lines.len() {
-            match lines[i].final_category {
-                Category::ProseGeneral | Category::Code => {
-                    // Check if this is a continuation (indented relative to bullet)
-                    if lines[i].indent > bullet_line.indent {
-                        continuation.push(lines[i].clone());
-                        i += 1;
-                    } else {
-                        break;
-                    }
-                }
-                Category::List => {
-                    // Check if this is a nested list
-                    if lines[i].indent > bullet_line.indent {
-                        let (nested_list, consumed) = parse_list_simple(lines, i);
-                        items.push(ListItem {
-                            bullet_line: bullet_line.clone(),
-                            continuation: continuation.clone(),
-                            nested: Some(Box::new(nested_list)),
-                        });
-                        i += consumed;
-                        break;
-                    } else {
-                        // Same or lesser indentation - end of current item
-                        break;
-                    }
-                }
-                _ => break,
-            }
+        while i < lines.len()
+            && lines[i].indent > bullet_line.indent
+            && is_item_prose(&lines[i], content_indent)
+        {
+            continuation.push(lines[i].clone());
+            i += 1;
         }
 
-        if items.is_empty() || items.last().unwrap().nested.is_none() {
-            items.push(ListItem {
-                bullet_line,
-                continuation,
-                nested: None,
-            });
-        }
+        let (children, consumed) = parse_item_children(lines, i, &bullet_line, content_indent);
+        i += consumed;
+
+        items.push(ListItem {
+            bullet_line,
+            continuation,
+            children,
+        });
     }
 
     let consumed = i - start;
@@ -216,6 +231,121 @@ fn parse_list_simple(lines: &[CatLine], start: usize) -> (ListNode, usize) {
     )
 }
 
+/// Parse the blocks indented under a list item after its first paragraph:
+/// nested lists, further paragraphs, code, quotes and tables. Blank lines
+/// belong to the item only when more of it follows them.
+fn parse_item_children(
+    lines: &[CatLine],
+    start: usize,
+    bullet_line: &CatLine,
+    content_indent: usize,
+) -> (Vec<ContChunk>, usize) {
+    let mut children = Vec::new();
+    let under_item = |line: &CatLine| {
+        line.indent > bullet_line.indent
+            && !matches!(line.final_category, Category::Comment | Category::Footer)
+    };
+    let is_prose = |line: &CatLine| is_item_prose(line, content_indent);
+    // Length of the run of lines from `i` matching `pred`
+    let run = |i: usize, pred: &dyn Fn(&CatLine) -> bool| {
+        lines[i..]
+            .iter()
+            .take_while(|line| under_item(line) && pred(line))
+            .count()
+    };
+
+    let mut i = start;
+    while i < lines.len() {
+        let line = &lines[i];
+
+        if line.final_category == Category::Empty {
+            let next = i + lines[i..]
+                .iter()
+                .take_while(|line| line.final_category == Category::Empty)
+                .count();
+            if next == lines.len() || !under_item(&lines[next]) {
+                break;
+            }
+            for blank in &lines[i..next] {
+                children.push(ContChunk::Paragraph(vec![blank.clone()]));
+            }
+            i = next;
+            continue;
+        }
+        if !under_item(line) {
+            break;
+        }
+
+        let len = match line.final_category {
+            Category::List => {
+                let (nested, consumed) = parse_list_simple(lines, i);
+                children.push(ContChunk::List(nested));
+                i += consumed;
+                continue;
+            }
+            Category::Table => {
+                let len = run(i, &|l| l.final_category == Category::Table);
+                children.push(ContChunk::Table(lines[i..i + len].to_vec()));
+                len
+            }
+            Category::Quote => {
+                let len = run(i, &|l| l.final_category == Category::Quote);
+                children.push(ContChunk::Quote(lines[i..i + len].to_vec()));
+                len
+            }
+            _ if opens_fence(line) => {
+                // Everything up to and including the closing fence
+                let (fence_char, fence_run) = fence_marker(line.text.trim()).unwrap_or_default();
+                let len = 1 + lines[i + 1..]
+                    .iter()
+                    .position(|l| is_closing_fence(l.text.trim(), fence_char, fence_run))
+                    .map_or(lines.len() - i - 1, |end| end + 1);
+                children.push(ContChunk::Code(lines[i..i + len].to_vec()));
+                len
+            }
+            _ if is_prose(line) => {
+                let len = run(i, &is_prose);
+                children.push(ContChunk::Paragraph(lines[i..i + len].to_vec()));
+                len
+            }
+            Category::Code => {
+                let len = run(i, &|l| {
+                    l.final_category == Category::Code && !is_prose(l) && !opens_fence(l)
+                });
+                children.push(ContChunk::Code(lines[i..i + len].to_vec()));
+                len
+            }
+            _ => break,
+        };
+        i += len;
+    }
+
+    (children, i - start)
+}
+
+/// Check if a line indented under a list item whose text starts at column
+/// `content_indent` is prose. As in Markdown, a line is indented code only
+/// if it is indented at least four columns past the item text; less indented
+/// lines are prose, even though the lexer classifies every line indented four
+/// columns as code. Fences and lines dense with symbols stay code.
+fn is_item_prose(line: &CatLine, content_indent: usize) -> bool {
+    match line.final_category {
+        Category::ProseGeneral | Category::ProseIntroduction | Category::URL => true,
+        Category::Code => {
+            let trimmed = line.text.trim();
+            line.indent < content_indent + 4
+                && fence_marker(trimmed).is_none()
+                && !looks_like_code(trimmed)
+        }
+        _ => false,
+    }
+}
+
+/// Check if a line opens a fenced code block
+fn opens_fence(line: &CatLine) -> bool {
+    line.final_category == Category::Code && fence_marker(line.text.trim()).is_some()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -240,6 +370,7 @@ mod tests {
             headline_width: 50,
             debug_svg: None,
             debug_trace: false,
+            ..Options::default()
         };
         let lexed = lex_lines(&lines, &opts);
         let classified = classify_with_context(lexed);
@@ -355,6 +486,38 @@ mod tests {
         assert!(has_comment, "Document should contain a comment chunk");
     }
 
+    #[test]
+    fn test_document_with_quotes() {
+        let lines = vec![
+            "Subject line",
+            "",
+            "The reviewer wrote:",
+            "> Please split this change",
+            ">> into two commits",
+            "",
+            "Done.",
+        ];
+
+        let opts = Options::default();
+        let lexed = lex_lines(&lines, &opts);
+        let classified = classify_with_context(lexed);
+        let document = build_document(classified);
+
+        let quotes: Vec<_> = document
+            .body_chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                ContChunk::Quote(lines) => Some(lines),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].len(), 2);
+        assert!(document.body_chunks.iter().any(
+            |chunk| matches!(chunk, ContChunk::Paragraph(lines) if lines[0].text == "The reviewer wrote:")
+        ));
+    }
+
     #[test]
     fn test_document_empty_body() {
         let lines = vec!["Subject line"];
@@ -395,6 +558,141 @@ mod tests {
         assert!(has_list, "Document should contain a list chunk");
     }
 
+    #[test]
+    fn test_document_sibling_after_nested_list() {
+        let lines = vec![
+            "Subject line",
+            "",
+            "- First item",
+            "  - Nested item",
+            "- Second item",
+            "- Third item",
+        ];
+
+        let opts = Options::default();
+        let lexed = lex_lines(&lines, &opts);
+        let classified = classify_with_context(lexed);
+        let document = build_document(classified);
+
+        let list = document
+            .body_chunks
+            .iter()
+            .find_map(|chunk| match chunk {
+                ContChunk::List(list) => Some(list),
+                _ => None,
+            })
+            .expect("Document should contain a list chunk");
+        let bullets: Vec<&str> = list
+            .items
+            .iter()
+            .map(|item| item.bullet_line.text.as_str())
+            .collect();
+        assert_eq!(
+            bullets,
+            vec!["- First item", "- Second item", "- Third item"]
+        );
+
+        let Some(ContChunk::List(nested)) = list.items[0].children.first() else {
+            panic!("First item should have a nested list");
+        };
+        assert_eq!(nested.items.len(), 1);
+        assert_eq!(nested.items[0].bullet_line.text, "  - Nested item");
+    }
+
+    #[test]
+    fn test_document_list_item_paragraphs() {
+        let lines = vec![
+            "Subject line",
+            "",
+            "- First item",
+            "  continues here.",
+            "",
+            "  A second paragraph of the first item.",
+            "",
+            "  ```",
+            "  make check",
+            "  ```",
+            "  - Nested item",
+            "",
+            "    Nested paragraph.",
+            "",
+            "        nested code",
+            "- Second item",
+            "",
+            "Closing paragraph.",
+        ];
+
+        let opts = Options::default();
+        let lexed = lex_lines(&lines, &opts);
+        let classified = classify_with_context(lexed);
+        let document = build_document(classified);
+
+        let ContChunk::List(list) = &document.body_chunks[1] else {
+            panic!("Expected a list chunk, got {:?}", document.body_chunks[1]);
+        };
+        assert_eq!(list.items.len(), 2);
+        assert_eq!(list.items[0].continuation.len(), 1);
+
+        let kinds = |children: &[ContChunk]| -> Vec<&'static str> {
+            children
+                .iter()
+                .map(|chunk| match chunk {
+                    ContChunk::Paragraph(lines) if lines[0].final_category == Category::Empty => {
+                        "empty"
+                    }
+                    ContChunk::Paragraph(_) => "paragraph",
+                    ContChunk::Code(_) => "code",
+                    ContChunk::List(_) => "list",
+                    _ => "other",
+                })
+                .collect()
+        };
+        let children = &list.items[0].children;
+        assert_eq!(
+            kinds(children),
+            vec!["empty", "paragraph", "empty", "code", "list"]
+        );
+        let ContChunk::List(nested) = &children[4] else {
+            unreachable!()
+        };
+        assert_eq!(
+            kinds(&nested.items[0].children),
+            vec!["empty", "paragraph", "empty", "code"]
+        );
+
+        // The blank line before the closing paragraph ends the list
+        assert!(list.items[1].children.is_empty());
+        assert!(matches!(
+            &document.body_chunks[3],
+            ContChunk::Paragraph(lines) if lines[0].text == "Closing paragraph."
+        ));
+    }
+
+    #[test]
+    fn test_document_list_item_code_line() {
+        let lines = vec![
+            "Subject line",
+            "",
+            "- Call the new helper",
+            "  x = foo(bar[0]);",
+            "  before returning.",
+        ];
+
+        let opts = Options::default();
+        let document = build_document(classify_with_context(lex_lines(&lines, &opts)));
+
+        // A line dense with symbols is code, not part of the item text
+        let ContChunk::List(list) = &document.body_chunks[1] else {
+            panic!("Expected a list chunk, got {:?}", document.body_chunks[1]);
+        };
+        let item = &list.items[0];
+        assert!(item.continuation.is_empty());
+        assert!(matches!(
+            &item.children[..],
+            [ContChunk::Code(_), ContChunk::Paragraph(_)]
+        ));
+    }
+
     #[test]
     fn test_document_only_footers() {
         let lines = vec!["Subject line", "", "Signed-off-by: Author <email>"];
@@ -408,4 +706,68 @@ mod tests {
         assert_eq!(document.footers.len(), 1);
         assert!(document.footers[0].text.contains("Signed-off-by"));
     }
+
+    #[test]
+    fn test_document_mid_body_trailer_is_content() {
+        let lines = vec![
+            "Subject line",
+            "",
+            "Fixes: the race in the scheduler when two workers pick up the same job at once",
+            "",
+            "More explanation that follows the trailer-looking line.",
+            "",
+            "Signed-off-by: Author <email>",
+            "",
+            "# Please enter the commit message",
+        ];
+
+        let opts = Options::default();
+        let lexed = lex_lines(&lines, &opts);
+        let classified = classify_with_context(lexed);
+        let document = build_document(classified);
+
+        let body_text: Vec<&str> = document
+            .body_chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                ContChunk::Paragraph(lines) => Some(lines[0].text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert!(body_text.iter().any(|t| t.starts_with("Fixes: the race")));
+        assert!(body_text.iter().any(|t| t.starts_with("More explanation")));
+
+        // The trailing block keeps its trailing blank and comment lines
+        assert_eq!(document.footers.len(), 3);
+        assert!(document.footers[0].text.starts_with("Signed-off-by"));
+    }
+
+    #[test]
+    fn test_document_fenced_code_is_single_chunk() {
+        let lines = vec![
+            "Subject line",
+            "",
+            "```",
+            "first sentence inside the fence",
+            "",
+            "second sentence inside the fence",
+            "```",
+        ];
+
+        let opts = Options::default();
+        let lexed = lex_lines(&lines, &opts);
+        let classified = classify_with_context(lexed);
+        let document = build_document(classified);
+
+        let code_chunks: Vec<_> = document
+            .body_chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                ContChunk::Code(lines) => Some(lines),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(code_chunks.len(), 1);
+        assert_eq!(code_chunks[0].len(), 5);
+    }
 }
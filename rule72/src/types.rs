@@ -4,16 +4,49 @@
 //! - Configuration options
 //! - Line categories and classification data
 //! - Document structure representation
+//!
+//! With the `serde` feature the document tree implements `Serialize`, so
+//! tools can consume rule72's parse (e.g. `rule72 --emit json`). Chunks are
+//! tagged with a `kind` field and line probabilities are ordered by category.
 
 use std::collections::HashMap;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::error::Error;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
 
 /// Formatting options for commit message reflow
 #[derive(Debug, Clone)]
 pub struct Options {
     pub width: usize,
     pub headline_width: usize,
+    /// Line breaking algorithm for paragraphs and list items
+    pub wrap: WrapAlgorithm,
+    /// Which paragraphs, list items and quotations are rewrapped, and how much of them
+    pub reflow: ReflowMode,
+    /// Renumber ordered list items sequentially within each list
+    pub renumber_lists: bool,
+    /// Use the first unordered marker (`-` or `*`) of each list for all its items
+    pub normalize_bullets: bool,
     pub debug_svg: Option<String>,
     pub debug_trace: bool,
+    /// Git comment character (`core.commentChar`); lines starting with it are comments
+    pub comment_char: char,
+    /// Additional trailer tags recognised as footers (e.g. `Change-Id`)
+    pub footer_tags: Vec<String>,
+    /// Trailer tags never treated as footers (e.g. `EN`)
+    pub footer_deny: Vec<String>,
+    /// Characters separating a trailer token from its value (`trailer.separators`)
+    pub trailer_separators: String,
+    /// Validate headlines as Conventional Commits when linting
+    pub conventional: bool,
+    /// Allowed Conventional Commits types (empty allows any)
+    pub conventional_types: Vec<String>,
+    /// Allowed Conventional Commits scopes (empty allows any)
+    pub conventional_scopes: Vec<String>,
 }
 
 impl Default for Options {
@@ -21,14 +54,102 @@ impl Default for Options {
         Self {
             width: 72,
             headline_width: 50,
+            wrap: WrapAlgorithm::Greedy,
+            reflow: ReflowMode::Overflow,
+            renumber_lists: false,
+            normalize_bullets: false,
             debug_svg: None,
             debug_trace: false,
+            comment_char: '#',
+            footer_tags: Vec::new(),
+            footer_deny: Vec::new(),
+            trailer_separators: ":".to_string(),
+            conventional: false,
+            conventional_types: Vec::new(),
+            conventional_scopes: Vec::new(),
+        }
+    }
+}
+
+impl Options {
+    /// Check that the options can produce a valid layout
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.width == 0 {
+            return Err(Error::InvalidOptions(
+                "width must be at least 1".to_string(),
+            ));
+        }
+        if self.comment_char.is_whitespace() {
+            return Err(Error::InvalidOptions(format!(
+                "comment character must not be whitespace, got {:?}",
+                self.comment_char
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Line breaking algorithm used when rewrapping text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WrapAlgorithm {
+    /// First-fit: fill each line as far as possible (fast)
+    #[default]
+    Greedy,
+    /// Minimum raggedness over the whole paragraph
+    Optimal,
+}
+
+impl FromStr for WrapAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "greedy" => Ok(WrapAlgorithm::Greedy),
+            "optimal" => Ok(WrapAlgorithm::Optimal),
+            _ => Err(format!(
+                "unknown wrap algorithm {s:?} (expected greedy or optimal)"
+            )),
+        }
+    }
+}
+
+/// How much of a paragraph, list item or quotation is rewrapped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReflowMode {
+    /// Rewrap the whole paragraph when any of its lines is over the width
+    #[default]
+    Overflow,
+    /// Rewrap only the lines over the width, flowing their excess into the
+    /// following lines until the original line breaks fit again
+    Minimal,
+    /// Join and rewrap all prose to fill the width, even if every line fits.
+    /// Prose directly next to code, without a blank line in between, is
+    /// only rewrapped when over the width. A refilled line that reads as
+    /// code (e.g. a run of paths) ends the paragraph, as it would on the
+    /// next run, and the text after it is wrapped on its own.
+    All,
+}
+
+impl FromStr for ReflowMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "overflow" => Ok(ReflowMode::Overflow),
+            "minimal" => Ok(ReflowMode::Minimal),
+            "all" => Ok(ReflowMode::All),
+            _ => Err(format!(
+                "unknown reflow mode {s:?} (expected overflow, minimal or all)"
+            )),
         }
     }
 }
 
 /// Line categories for classification
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub enum Category {
     ProseIntroduction,
     ProseGeneral,
@@ -36,6 +157,8 @@ pub enum Category {
     Code,
     Table,
     URL,
+    /// `>`-prefixed quotation (review comments, error messages, emails)
+    Quote,
     Empty,
     Comment,
     Footer,
@@ -43,14 +166,30 @@ pub enum Category {
 
 /// Categorical line with classification probabilities
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct CatLine {
     pub text: String,
+    /// 0-based index of the line in the input
     pub line_number: usize,
     pub indent: usize,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_sorted"))]
     pub probabilities: HashMap<Category, f32>,
     pub final_category: Category,
 }
 
+/// Serialize probabilities in category order so output is deterministic
+#[cfg(feature = "serde")]
+fn serialize_sorted<S: Serializer>(
+    probabilities: &HashMap<Category, f32>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(
+        probabilities
+            .iter()
+            .collect::<std::collections::BTreeMap<_, _>>(),
+    )
+}
+
 /// Contiguous chunk types in the tree structure
 #[derive(Debug)]
 pub enum ContChunk {
@@ -59,23 +198,60 @@ pub enum ContChunk {
     List(ListNode),
     Code(Vec<CatLine>),
     Comment(Vec<CatLine>),
+    /// Consecutive `>`-quoted lines, possibly at several nesting levels
+    Quote(Vec<CatLine>),
+}
+
+/// Serialized as `{"kind": "paragraph", "lines": [...]}`, or for lists
+/// `{"kind": "list", "introduction": [...], "items": [...]}`
+#[cfg(feature = "serde")]
+impl Serialize for ContChunk {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let (kind, lines) = match self {
+            ContChunk::Table(lines) => ("table", lines),
+            ContChunk::Paragraph(lines) => ("paragraph", lines),
+            ContChunk::Code(lines) => ("code", lines),
+            ContChunk::Comment(lines) => ("comment", lines),
+            ContChunk::Quote(lines) => ("quote", lines),
+            ContChunk::List(list) => {
+                let mut state = serializer.serialize_struct("ContChunk", 3)?;
+                state.serialize_field("kind", "list")?;
+                state.serialize_field("introduction", &list.introduction)?;
+                state.serialize_field("items", &list.items)?;
+                return state.end();
+            }
+        };
+        let mut state = serializer.serialize_struct("ContChunk", 2)?;
+        state.serialize_field("kind", kind)?;
+        state.serialize_field("lines", lines)?;
+        state.end()
+    }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct ListNode {
     pub introduction: Vec<CatLine>, // Introduction lines that precede the list
     pub items: Vec<ListItem>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct ListItem {
     pub bullet_line: CatLine,
+    /// Lines continuing the item's first paragraph
     pub continuation: Vec<CatLine>,
-    pub nested: Option<Box<ListNode>>,
+    /// Blocks indented under the item after its first paragraph: further
+    /// paragraphs, code, quotes, tables and nested lists, with blank lines
+    /// between them as single-line empty paragraphs like in the body
+    pub children: Vec<ContChunk>,
 }
 
 /// Document structure
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Document {
     pub headline: Option<CatLine>,
     pub body_chunks: Vec<ContChunk>,
@@ -91,8 +267,19 @@ mod tests {
         let opts = Options::default();
         assert_eq!(opts.width, 72);
         assert_eq!(opts.headline_width, 50);
+        assert_eq!(opts.wrap, WrapAlgorithm::Greedy);
+        assert_eq!(opts.reflow, ReflowMode::Overflow);
+        assert!(!opts.renumber_lists);
+        assert!(!opts.normalize_bullets);
         assert_eq!(opts.debug_svg, None);
-        assert_eq!(opts.debug_trace, false);
+        assert!(!opts.debug_trace);
+        assert_eq!(opts.comment_char, '#');
+        assert!(opts.footer_tags.is_empty());
+        assert!(opts.footer_deny.is_empty());
+        assert_eq!(opts.trailer_separators, ":");
+        assert!(!opts.conventional);
+        assert!(opts.conventional_types.is_empty());
+        assert!(opts.conventional_scopes.is_empty());
     }
 
     #[test]
@@ -100,15 +287,54 @@ mod tests {
         let opts1 = Options {
             width: 80,
             headline_width: 60,
+            wrap: WrapAlgorithm::Optimal,
+            reflow: ReflowMode::Minimal,
+            renumber_lists: true,
+            normalize_bullets: true,
             debug_svg: Some("test.svg".to_string()),
             debug_trace: true,
+            comment_char: ';',
+            footer_tags: vec!["Change-Id".to_string()],
+            footer_deny: vec!["EN".to_string()],
+            trailer_separators: ":#".to_string(),
+            conventional: true,
+            conventional_types: vec!["feat".to_string()],
+            conventional_scopes: vec!["api".to_string()],
         };
         let opts2 = opts1.clone();
 
         assert_eq!(opts1.width, opts2.width);
         assert_eq!(opts1.headline_width, opts2.headline_width);
+        assert_eq!(opts1.wrap, opts2.wrap);
+        assert_eq!(opts1.reflow, opts2.reflow);
+        assert_eq!(opts1.renumber_lists, opts2.renumber_lists);
+        assert_eq!(opts1.normalize_bullets, opts2.normalize_bullets);
         assert_eq!(opts1.debug_svg, opts2.debug_svg);
         assert_eq!(opts1.debug_trace, opts2.debug_trace);
+        assert_eq!(opts1.comment_char, opts2.comment_char);
+        assert_eq!(opts1.footer_tags, opts2.footer_tags);
+        assert_eq!(opts1.footer_deny, opts2.footer_deny);
+        assert_eq!(opts1.trailer_separators, opts2.trailer_separators);
+        assert_eq!(opts1.conventional, opts2.conventional);
+        assert_eq!(opts1.conventional_types, opts2.conventional_types);
+        assert_eq!(opts1.conventional_scopes, opts2.conventional_scopes);
+    }
+
+    #[test]
+    fn test_options_validate() {
+        assert_eq!(Options::default().validate(), Ok(()));
+
+        let opts = Options {
+            width: 0,
+            ..Options::default()
+        };
+        assert!(matches!(opts.validate(), Err(Error::InvalidOptions(_))));
+
+        let opts = Options {
+            comment_char: ' ',
+            ..Options::default()
+        };
+        assert!(matches!(opts.validate(), Err(Error::InvalidOptions(_))));
     }
 
     #[test]
@@ -118,6 +344,46 @@ mod tests {
         assert_ne!(Category::Code, Category::Comment);
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_document_serialize() {
+        let line = |text: &str, line_number, category| CatLine {
+            text: text.to_string(),
+            line_number,
+            indent: 0,
+            probabilities: HashMap::from([(category, 0.75), (Category::ProseGeneral, 0.25)]),
+            final_category: category,
+        };
+        let document = Document {
+            headline: None,
+            body_chunks: vec![
+                ContChunk::Code(vec![line("x = 1", 0, Category::Code)]),
+                ContChunk::List(ListNode {
+                    introduction: Vec::new(),
+                    items: vec![ListItem {
+                        bullet_line: line("- item", 1, Category::List),
+                        continuation: Vec::new(),
+                        children: Vec::new(),
+                    }],
+                }),
+            ],
+            footers: Vec::new(),
+        };
+
+        let json = serde_json::to_value(&document).unwrap();
+        assert_eq!(json["body_chunks"][0]["kind"], "code");
+        assert_eq!(json["body_chunks"][0]["lines"][0]["final_category"], "Code");
+        assert_eq!(json["body_chunks"][1]["kind"], "list");
+        assert_eq!(
+            json["body_chunks"][1]["items"][0]["bullet_line"]["line_number"],
+            1
+        );
+
+        // Probabilities are ordered by category, not by hash
+        let text = serde_json::to_string(&document.body_chunks[1]).unwrap();
+        assert!(text.contains(r#""probabilities":{"ProseGeneral":0.25,"List":0.75}"#));
+    }
+
     #[test]
     fn test_category_hash() {
         let mut map = HashMap::new();
@@ -228,7 +494,7 @@ mod tests {
         let list_item = ListItem {
             bullet_line,
             continuation: vec![],
-            nested: None,
+            children: vec![],
         };
 
         let list_node = ListNode {
@@ -240,7 +506,7 @@ mod tests {
         assert_eq!(list_node.items.len(), 1);
         assert_eq!(list_node.items[0].bullet_line.text, "- First item");
         assert_eq!(list_node.items[0].continuation.len(), 0);
-        assert!(list_node.items[0].nested.is_none());
+        assert!(list_node.items[0].children.is_empty());
     }
 
     #[test]
@@ -267,7 +533,7 @@ mod tests {
         let nested_item = ListItem {
             bullet_line: nested_bullet,
             continuation: vec![],
-            nested: None,
+            children: vec![],
         };
 
         let nested_node = ListNode {
@@ -278,14 +544,16 @@ mod tests {
         let parent_item = ListItem {
             bullet_line,
             continuation: vec![],
-            nested: Some(Box::new(nested_node)),
+            children: vec![ContChunk::List(nested_node)],
         };
 
-        assert!(parent_item.nested.is_some());
+        assert_eq!(parent_item.children.len(), 1);
 
-        if let Some(ref nested) = parent_item.nested {
+        if let ContChunk::List(ref nested) = parent_item.children[0] {
             assert_eq!(nested.items.len(), 1);
             assert_eq!(nested.items[0].bullet_line.text, "  - Nested item");
+        } else {
+            panic!("Expected a nested list child");
         }
     }
 
@@ -306,6 +574,7 @@ mod tests {
         let paragraph = ContChunk::Paragraph(vec![line.clone()]);
         let code = ContChunk::Code(vec![line.clone()]);
         let comment = ContChunk::Comment(vec![line.clone()]);
+        let quote = ContChunk::Quote(vec![line.clone()]);
         let table = ContChunk::Table(vec![line]);
 
         match paragraph {
@@ -323,6 +592,11 @@ mod tests {
             _ => panic!("Expected Comment chunk"),
         }
 
+        match quote {
+            ContChunk::Quote(lines) => assert_eq!(lines.len(), 1),
+            _ => panic!("Expected Quote chunk"),
+        }
+
         match table {
             ContChunk::Table(lines) => assert_eq!(lines.len(), 1),
             _ => panic!("Expected Table chunk"),
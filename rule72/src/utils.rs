@@ -7,6 +7,8 @@
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
+use crate::types::WrapAlgorithm;
+
 /// Debug trace macro that includes file and line information
 macro_rules! debug_trace {
     ($opts:expr, $fmt:literal $(, $($arg:tt)*)?) => {
@@ -27,6 +29,12 @@ pub fn count_indent(line: &str) -> usize {
         .sum()
 }
 
+/// Display width of a line prefix such as a list marker or quote markers
+/// with their indentation. Tabs count as four columns, as in [`count_indent`].
+pub fn prefix_width(prefix: &str) -> usize {
+    count_indent(prefix) + display_width(prefix.trim_start())
+}
+
 /// Count special characters that might indicate code content.
 /// Returns the number of characters that are typically found in code
 /// (symbols, punctuation, etc.) rather than natural language.
@@ -36,6 +44,12 @@ pub fn count_special_chars(s: &str) -> usize {
         .count()
 }
 
+/// Check if more than 30% of a trimmed line are special characters, which
+/// the lexer takes as a sign of code rather than prose.
+pub fn looks_like_code(trimmed: &str) -> bool {
+    !trimmed.is_empty() && count_special_chars(trimmed) as f32 / trimmed.len() as f32 > 0.3
+}
+
 /// Check if a line matches Git footer patterns (tag: value format).
 /// Recognizes common Git trailers like "Signed-off-by:", "Co-authored-by:", etc.
 pub fn is_footer_line(line: &str) -> bool {
@@ -68,8 +82,126 @@ pub fn is_footer_line(line: &str) -> bool {
     false
 }
 
+/// The cut line Git writes below the comment character in `git commit -v`
+/// templates; everything after it is ignored by Git.
+const SCISSORS: &str = "------------------------ >8 ------------------------";
+
+/// Check if a line is a comment: it starts with the Git comment character
+/// (`core.commentChar`, default `#`). Git strips no other lines, so `//`
+/// is ordinary text.
+pub fn is_comment_line(line: &str, comment_char: char) -> bool {
+    line.starts_with(comment_char)
+}
+
+/// Check if a line is Git's scissors line, e.g.
+/// `# ------------------------ >8 ------------------------`.
+pub fn is_scissors_line(line: &str, comment_char: char) -> bool {
+    line.trim_end()
+        .strip_prefix(comment_char)
+        .and_then(|rest| rest.strip_prefix(' '))
+        == Some(SCISSORS)
+}
+
+/// Split `input` at the scissors line. Returns the message before it and the
+/// verbatim remainder starting at the scissors line (empty if there is none).
+pub fn split_at_scissors(input: &str, comment_char: char) -> (&str, &str) {
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        if is_scissors_line(line.trim_end_matches(['\r', '\n']), comment_char) {
+            return input.split_at(offset);
+        }
+        offset += line.len();
+    }
+    (input, "")
+}
+
+/// Detect a Markdown code fence (three or more backticks or tildes).
+/// Returns the fence character and its run length; an info string such as
+/// the `rust` in ```` ```rust ```` may follow the fence.
+pub fn fence_marker(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start();
+    let fence_char = trimmed.chars().next()?;
+    if fence_char != '`' && fence_char != '~' {
+        return None;
+    }
+
+    let run = trimmed.chars().take_while(|&c| c == fence_char).count();
+    if run < 3 {
+        return None;
+    }
+
+    // Backtick fences may not contain backticks in their info string
+    if fence_char == '`' && trimmed[run..].contains('`') {
+        return None;
+    }
+    Some((fence_char, run))
+}
+
+/// Check if a line closes a fence opened with `fence_char` repeated `run` times.
+/// The closing fence must be at least as long as the opener and carry no info string.
+pub fn is_closing_fence(line: &str, fence_char: char, run: usize) -> bool {
+    match fence_marker(line) {
+        Some((c, len)) => c == fence_char && len >= run && line.trim()[len..].is_empty(),
+        None => false,
+    }
+}
+
+/// Check if a line starts with one of the user-supplied footer tags.
+/// Tags match case-insensitively and may be given with or without the colon.
+pub fn has_footer_tag(line: &str, tags: &[String]) -> bool {
+    tags.iter().any(|tag| {
+        let tag = tag.trim_end_matches(':');
+        match line.get(..tag.len()) {
+            Some(prefix) => {
+                !tag.is_empty()
+                    && prefix.eq_ignore_ascii_case(tag)
+                    && line[tag.len()..].starts_with(':')
+            }
+            None => false,
+        }
+    })
+}
+
+/// Extract the token of a Git trailer line: `Token: value`, or `Token #value`
+/// when `#` is among the `separators`. Tokens consist of alphanumerics and
+/// hyphens; Conventional Commits' `BREAKING CHANGE` is accepted as well.
+/// Bare URLs such as `https://...` are not trailers.
+pub fn trailer_token<'a>(line: &'a str, separators: &str) -> Option<&'a str> {
+    let token_len = if line.starts_with("BREAKING CHANGE") {
+        "BREAKING CHANGE".len()
+    } else {
+        line.find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+            .unwrap_or(line.len())
+    };
+    let token = &line[..token_len];
+    if token.is_empty() || token.starts_with('-') {
+        return None;
+    }
+
+    let rest = line[token_len..].trim_start_matches([' ', '\t']);
+    let separator = rest.chars().next()?;
+    if !separators.contains(separator) || rest[separator.len_utf8()..].starts_with("//") {
+        return None;
+    }
+    Some(token)
+}
+
+/// Check if a trailer token is Conventional Commits' `BREAKING CHANGE` or
+/// its synonym `BREAKING-CHANGE`
+pub fn is_breaking_change(token: &str) -> bool {
+    matches!(token, "BREAKING CHANGE" | "BREAKING-CHANGE")
+}
+
+/// Check if a trailer token is in a list of tags (case-insensitive,
+/// tags may be given with or without the colon).
+pub fn is_listed_tag(token: &str, tags: &[String]) -> bool {
+    tags.iter()
+        .any(|tag| tag.trim_end_matches(':').eq_ignore_ascii_case(token))
+}
+
 /// Detect if a line is a list item (bullet, numbered, or emoji).
-/// Recognizes common list markers including markdown bullets, numbers, and emoji.
+/// Recognizes common list markers including markdown bullets, numbers, and emoji,
+/// with or without a GitHub task-list checkbox (`- [ ] todo`, `- [x] done`).
 pub fn is_list_item(line: &str) -> bool {
     let trimmed = line.trim_start();
     if trimmed.starts_with("* ") || trimmed.starts_with("- ") {
@@ -98,9 +230,23 @@ pub fn is_list_item(line: &str) -> bool {
     false
 }
 
+/// Length of a GitHub task-list checkbox (`[ ]`, `[x]` or `[X]`) at the
+/// start of `text`, if it is followed by a space or ends the text.
+pub fn task_checkbox_len(text: &str) -> Option<usize> {
+    let checkbox = ["[ ]", "[x]", "[X]"]
+        .into_iter()
+        .find(|checkbox| text.starts_with(checkbox))?;
+    match text[checkbox.len()..].chars().next() {
+        None | Some(' ') => Some(checkbox.len()),
+        _ => None,
+    }
+}
+
 /// Extract the bullet prefix from a list item line.
 /// Returns the bullet marker (including trailing space) that should be
-/// preserved when wrapping list content.
+/// preserved when wrapping list content. A task-list checkbox such as
+/// `- [ ] ` is part of the prefix, so continuation lines align with the
+/// item text after it.
 pub fn extract_bullet_prefix(line: &str) -> &str {
     let trimmed_start = line.trim_start_matches(' ');
     let offset = line.len() - trimmed_start.len();
@@ -115,58 +261,341 @@ pub fn extract_bullet_prefix(line: &str) -> &str {
             break;
         }
     }
-    while idx < line.len() && &line[idx..idx + 1] == " " {
+    while line.as_bytes().get(idx) == Some(&b' ') {
         idx += 1;
     }
+    if let Some(len) = task_checkbox_len(&line[idx..]) {
+        idx += len;
+        while line.as_bytes().get(idx) == Some(&b' ') {
+            idx += 1;
+        }
+    }
     &line[..idx]
 }
 
+/// Split an ordered list marker such as `3.` or `10)` into its number and
+/// delimiter. Returns `None` for bullets and other markers.
+pub fn ordered_marker(marker: &str) -> Option<(usize, char)> {
+    let delimiter = marker.chars().next_back()?;
+    if delimiter != '.' && delimiter != ')' {
+        return None;
+    }
+    let digits = &marker[..marker.len() - 1];
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((digits.parse().ok()?, delimiter))
+}
+
+/// Extract the quote prefix of a `>`-quoted line: leading indentation and
+/// every `>` marker, each with at most one following space. Nested quotes
+/// may be written `> > text` or `>> text`. A run of `>` only counts as
+/// markers if a space or the end of the line follows it, so prose such as
+/// `>=3.0 is required` is not quoted. Returns an empty string if the line
+/// is not quoted.
+pub fn quote_prefix(line: &str) -> &str {
+    let indented = line.trim_start_matches([' ', '\t']);
+    let mut rest = indented;
+    loop {
+        let after = rest.trim_start_matches('>');
+        if after.len() == rest.len() || !(after.is_empty() || after.starts_with(' ')) {
+            break;
+        }
+        rest = after.strip_prefix(' ').unwrap_or(after);
+    }
+    if rest.len() == indented.len() {
+        return "";
+    }
+    &line[..line.len() - rest.len()]
+}
+
+/// Check if a line is a `>` quotation (see [`quote_prefix`])
+pub fn is_quote_line(line: &str) -> bool {
+    !quote_prefix(line).is_empty()
+}
+
+/// Nesting level of a quote prefix (the number of `>` markers)
+pub fn quote_depth(prefix: &str) -> usize {
+    prefix.matches('>').count()
+}
+
+/// An unbreakable piece of text for wrapping
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment<'a> {
+    pub text: &'a str,
+    pub width: usize,
+    /// Whether a space separates this segment from the previous one
+    pub space_before: bool,
+    /// Whether the line may not be broken before this segment
+    pub glued: bool,
+}
+
+/// Check if a character is a Chinese or Japanese character or fullwidth
+/// punctuation, which is written without spaces between words.
+pub fn is_cjk(c: char) -> bool {
+    matches!(
+        c,
+        '\u{2E80}'..='\u{2FDF}'   // CJK radicals, Kangxi radicals
+            | '\u{3000}'..='\u{303F}' // CJK symbols and punctuation
+            | '\u{3040}'..='\u{30FF}' // Hiragana, Katakana
+            | '\u{3100}'..='\u{312F}' // Bopomofo
+            | '\u{31F0}'..='\u{31FF}' // Katakana phonetic extensions
+            | '\u{3400}'..='\u{4DBF}' // CJK extension A
+            | '\u{4E00}'..='\u{9FFF}' // CJK unified ideographs
+            | '\u{F900}'..='\u{FAFF}' // CJK compatibility ideographs
+            | '\u{FF00}'..='\u{FFEF}' // Halfwidth and fullwidth forms
+            | '\u{20000}'..='\u{3FFFF}' // CJK extensions B and later
+    )
+}
+
+/// Split text into wrappable segments.
+///
+/// Words are separated at whitespace as before. Within a word, the Unicode
+/// line breaking algorithm (UAX #14) adds break opportunities between CJK
+/// characters, so text without spaces can still be wrapped while kinsoku
+/// rules (no break before `。` or `」`, none after `「`) are respected.
+/// Breaks elsewhere inside a word, such as after a hyphen or between Latin
+/// and CJK text, are not used: [`join_lines`] would insert a space there
+/// when the wrapped lines are joined again. Likewise a space between two
+/// CJK characters is not a break opportunity, as joining would drop it.
+pub fn segments(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut previous_word: Option<&str> = None;
+    for word in text.split_whitespace() {
+        let cjk_space = previous_word
+            .is_some_and(|prev| prev.chars().next_back().is_some_and(is_cjk))
+            && word.chars().next().is_some_and(is_cjk);
+        previous_word = Some(word);
+        let mut start = 0;
+        if word.chars().any(is_cjk) {
+            for (pos, _) in unicode_linebreak::linebreaks(word) {
+                if pos == word.len() {
+                    break;
+                }
+                let before = word[..pos].chars().next_back().is_some_and(is_cjk);
+                let after = word[pos..].chars().next().is_some_and(is_cjk);
+                if before && after {
+                    segments.push(Segment {
+                        text: &word[start..pos],
+                        width: display_width(&word[start..pos]),
+                        space_before: start == 0,
+                        glued: start == 0 && cjk_space,
+                    });
+                    start = pos;
+                }
+            }
+        }
+        segments.push(Segment {
+            text: &word[start..],
+            width: display_width(&word[start..]),
+            space_before: start == 0,
+            glued: start == 0 && cjk_space,
+        });
+    }
+    segments
+}
+
+/// Join the lines of a paragraph with spaces, except between two CJK
+/// characters where Chinese and Japanese text has no space.
+pub fn join_lines<'a>(lines: impl IntoIterator<Item = &'a str>) -> String {
+    let mut joined = String::new();
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let cjk_boundary = joined.chars().next_back().is_some_and(is_cjk)
+            && line.chars().next().is_some_and(is_cjk);
+        if !joined.is_empty() && !cjk_boundary {
+            joined.push(' ');
+        }
+        joined.push_str(line);
+    }
+    joined
+}
+
+/// Render segments as one line, inserting spaces where the source had them
+fn render_line(segments: &[Segment]) -> String {
+    let mut line = String::new();
+    for (idx, segment) in segments.iter().enumerate() {
+        if idx > 0 && segment.space_before {
+            line.push(' ');
+        }
+        line.push_str(segment.text);
+    }
+    line
+}
+
+/// Display width of segments rendered as one line
+fn segments_width(segments: &[Segment]) -> usize {
+    segments
+        .iter()
+        .enumerate()
+        .map(|(idx, segment)| segment.width + usize::from(idx > 0 && segment.space_before))
+        .sum()
+}
+
 /// Wrap text to specified width using greedy wrapping algorithm.
-/// Preserves word boundaries and handles Unicode characters correctly.
+/// Preserves word boundaries and handles Unicode characters correctly;
+/// CJK text is broken between characters (see [`segments`]).
 /// Words longer than the width limit are placed on their own line.
 pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
     if text.trim().is_empty() {
         return vec![String::new()];
     }
+    break_greedily(&segments(text), width)
+}
+
+/// Split segments into units that must stay on one line: a segment and the
+/// segments glued to it.
+fn units(segments: &[Segment]) -> Vec<std::ops::Range<usize>> {
+    let mut units: Vec<std::ops::Range<usize>> = Vec::new();
+    for (idx, segment) in segments.iter().enumerate() {
+        match units.last_mut() {
+            Some(unit) if segment.glued => unit.end = idx + 1,
+            _ => units.push(idx..idx + 1),
+        }
+    }
+    units
+}
 
+/// Greedy line breaking of segments, keeping glued segments together
+fn break_greedily(segments: &[Segment], width: usize) -> Vec<String> {
     let mut lines = Vec::new();
-    let mut current_line = String::new();
-    let mut current_width = 0;
+    let mut start = 0;
+    let mut line_width = 0;
 
-    for word in text.split_whitespace() {
-        let word_width = display_width(word);
-
-        // Handle words longer than width limit
-        if word_width > width {
-            // If current line has content, finish it first
-            if !current_line.is_empty() {
-                lines.push(current_line);
-                current_line = String::new();
-                current_width = 0;
+    for unit in units(segments) {
+        let unit_width = segments_width(&segments[unit.clone()]);
+        let gap = usize::from(unit.start > start && segments[unit.start].space_before);
+
+        // Handle words longer than width limit: they get a line of their own
+        if unit_width > width {
+            if start < unit.start {
+                lines.push(render_line(&segments[start..unit.start]));
             }
-            // Add the long word as its own line
-            lines.push(word.to_string());
-            continue;
+            lines.push(render_line(&segments[unit.clone()]));
+            start = unit.end;
+            line_width = 0;
+        } else if line_width + gap + unit_width <= width {
+            line_width += gap + unit_width;
+        } else {
+            lines.push(render_line(&segments[start..unit.start]));
+            start = unit.start;
+            line_width = unit_width;
         }
+    }
+
+    if start < segments.len() {
+        lines.push(render_line(&segments[start..]));
+    }
+
+    lines
+}
 
-        if current_line.is_empty() {
-            current_line.push_str(word);
-            current_width = word_width;
-        } else if current_width + 1 + word_width <= width {
-            current_line.push(' ');
-            current_line.push_str(word);
-            current_width += 1 + word_width;
+/// Wrap text to the specified width with the chosen algorithm.
+pub fn wrap_text_with(text: &str, width: usize, algorithm: WrapAlgorithm) -> Vec<String> {
+    wrap_text_guarded(text, width, algorithm, |_| false)
+}
+
+/// Wrap text like [`wrap_text_with`], but never start a line with text for
+/// which `opens_block` returns true, such as a list marker that would turn
+/// the wrapped line into a list item when the output is read back in. The
+/// predicate sees the first two segments of the would-be line, or the last
+/// segment followed by a space, since more text may follow it on the next
+/// run (e.g. when minimal reflow carries it into the next line). Such
+/// segments stay on the previous line, even if it then overflows.
+pub fn wrap_text_guarded(
+    text: &str,
+    width: usize,
+    algorithm: WrapAlgorithm,
+    opens_block: impl Fn(&str) -> bool,
+) -> Vec<String> {
+    let mut segments = segments(text);
+    if segments.is_empty() {
+        return vec![String::new()];
+    }
+
+    for idx in 1..segments.len() {
+        let line_start = if idx + 1 < segments.len() {
+            render_line(&segments[idx..idx + 2])
         } else {
-            lines.push(current_line);
-            current_line = word.to_string();
-            current_width = word_width;
+            format!("{} ", segments[idx].text)
+        };
+        segments[idx].glued |= opens_block(&line_start);
+    }
+
+    match algorithm {
+        WrapAlgorithm::Greedy => break_greedily(&segments, width),
+        WrapAlgorithm::Optimal => break_runs_optimally(&segments, width),
+    }
+}
+
+/// Wrap text to specified width minimising raggedness (Knuth–Plass style).
+/// Minimises the sum of squared trailing space over all lines but the last,
+/// so lines come out more even than with [`wrap_text`]. Words longer than the
+/// width limit are placed on their own line, as with the greedy algorithm.
+pub fn wrap_text_optimal(text: &str, width: usize) -> Vec<String> {
+    let segments = segments(text);
+    if segments.is_empty() {
+        return vec![String::new()];
+    }
+    break_runs_optimally(&segments, width)
+}
+
+/// Optimal line breaking around words longer than `width`
+fn break_runs_optimally(segments: &[Segment], width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut run_start = 0;
+    for unit in units(segments) {
+        // Overlong words split the text into independently wrapped runs
+        if segments_width(&segments[unit.clone()]) > width {
+            lines.extend(break_optimally(&segments[run_start..unit.start], width));
+            lines.push(render_line(&segments[unit.clone()]));
+            run_start = unit.end;
         }
     }
+    lines.extend(break_optimally(&segments[run_start..], width));
+    lines
+}
 
-    if !current_line.is_empty() {
-        lines.push(current_line);
+/// Minimum-raggedness line breaking of units that each fit within `width`.
+/// Lines never start with a glued segment.
+fn break_optimally(segments: &[Segment], width: usize) -> Vec<String> {
+    let n = segments.len();
+
+    // cost[i] is the minimal cost of laying out segments[i..]; next[i] is
+    // where the first line of that layout ends
+    let mut cost = vec![0u64; n + 1];
+    let mut next = vec![n; n + 1];
+    for i in (0..n).rev() {
+        cost[i] = u64::MAX;
+        let mut line_width = 0;
+        for j in i + 1..=n {
+            let segment = &segments[j - 1];
+            line_width += segment.width + usize::from(j > i + 1 && segment.space_before);
+            if line_width > width {
+                break;
+            }
+            if j < n && segments[j].glued {
+                continue;
+            }
+            let slack = (width - line_width) as u64;
+            let line_cost = if j == n { 0 } else { slack * slack };
+            let total = line_cost.saturating_add(cost[j]);
+            if total <= cost[i] {
+                cost[i] = total;
+                next[i] = j;
+            }
+        }
     }
 
+    let mut lines = Vec::new();
+    let mut i = 0;
+    while i < n {
+        lines.push(render_line(&segments[i..next[i]]));
+        i = next[i];
+    }
     lines
 }
 
@@ -214,6 +643,112 @@ mod tests {
         assert_eq!(result, vec!["🔥 hello", "世界"]);
     }
 
+    #[test]
+    fn test_wrap_text_optimal() {
+        let text = "aaa bb cc ddddd";
+        // Greedy fills the first line and leaves a ragged second line
+        assert_eq!(wrap_text(text, 6), vec!["aaa bb", "cc", "ddddd"]);
+        assert_eq!(wrap_text_optimal(text, 6), vec!["aaa", "bb cc", "ddddd"]);
+
+        assert_eq!(
+            wrap_text_optimal("short verylongwordthatexceedslimit more", 10),
+            vec!["short", "verylongwordthatexceedslimit", "more"]
+        );
+        assert_eq!(wrap_text_optimal("", 10), vec![""]);
+        // CJK characters are two columns wide and may be broken between
+        assert_eq!(
+            wrap_text_optimal("世界 世界 世界", 8),
+            vec!["世界 世", "界 世界"]
+        );
+    }
+
+    #[test]
+    fn test_wrap_text_optimal_respects_width() {
+        let text = "The quick brown fox jumps over the lazy dog and keeps running through the forest until nightfall";
+        for width in 10..40 {
+            let lines = wrap_text_optimal(text, width);
+            assert!(lines.iter().all(|line| display_width(line) <= width));
+            assert_eq!(lines.join(" "), text);
+        }
+    }
+
+    #[test]
+    fn test_wrap_text_guarded() {
+        let text = "one two three - four five";
+        let opens_block = |line: &str| line.starts_with("- ");
+        assert_eq!(wrap_text(text, 14), vec!["one two three", "- four five"]);
+        for algorithm in [WrapAlgorithm::Greedy, WrapAlgorithm::Optimal] {
+            assert_eq!(
+                wrap_text_guarded(text, 14, algorithm, opens_block),
+                vec!["one two", "three - four", "five"]
+            );
+        }
+
+        // Glued segments overflow rather than start a line
+        assert_eq!(
+            wrap_text_guarded("averylongword - x", 12, WrapAlgorithm::Greedy, opens_block),
+            vec!["averylongword -", "x"]
+        );
+
+        // A trailing marker may be followed by more text on the next run
+        assert_eq!(
+            wrap_text_guarded("one two -", 7, WrapAlgorithm::Greedy, opens_block),
+            vec!["one", "two -"]
+        );
+    }
+
+    #[test]
+    fn test_segments_cjk() {
+        let texts: Vec<&str> = segments("日本語の文章です。")
+            .iter()
+            .map(|segment| segment.text)
+            .collect();
+        // No break before the full stop (kinsoku)
+        assert_eq!(
+            texts,
+            vec!["日", "本", "語", "の", "文", "章", "で", "す。"]
+        );
+
+        // Latin words keep their hyphens and spacing
+        let latin = segments("well-known fix");
+        assert_eq!(latin.len(), 2);
+        assert!(latin.iter().all(|segment| segment.space_before));
+
+        // Mixed text only breaks between CJK characters, since joining the
+        // lines again would put a space after `Rust`
+        let mixed = segments("Rustで書く");
+        assert_eq!(mixed[0].text, "Rustで");
+        assert_eq!(mixed[1].text, "書");
+        assert!(!mixed[1].space_before);
+        assert_eq!(join_lines(["Rustで", "書く"]), "Rustで書く");
+
+        // A space between CJK characters is kept, so it is not a break
+        let spaced = segments("です。 次に");
+        assert_eq!(spaced[2].text, "次");
+        assert!(spaced[2].space_before && spaced[2].glued);
+        assert!(!segments("です。 next")[2].glued);
+    }
+
+    #[test]
+    fn test_wrap_text_cjk() {
+        let text = "これは日本語で書かれたコミットメッセージの本文です。";
+        for wrap in [wrap_text(text, 20), wrap_text_optimal(text, 20)] {
+            assert!(wrap.len() > 1);
+            assert!(wrap.iter().all(|line| display_width(line) <= 20));
+            assert!(wrap.iter().all(|line| !line.contains(' ')));
+            assert!(wrap.iter().all(|line| !line.starts_with('。')));
+            assert_eq!(wrap.concat(), text);
+        }
+    }
+
+    #[test]
+    fn test_join_lines() {
+        assert_eq!(join_lines(["hello", "world"]), "hello world");
+        assert_eq!(join_lines(["日本語の", "文章です。"]), "日本語の文章です。");
+        assert_eq!(join_lines(["Rust で", "書く"]), "Rust で書く");
+        assert_eq!(join_lines(["日本語", "text"]), "日本語 text");
+    }
+
     #[test]
     fn test_count_indent() {
         assert_eq!(count_indent("hello"), 0);
@@ -226,6 +761,15 @@ mod tests {
         assert_eq!(count_indent("   "), 3);
     }
 
+    #[test]
+    fn test_prefix_width() {
+        assert_eq!(prefix_width("- "), 2);
+        assert_eq!(prefix_width("  10. "), 6);
+        assert_eq!(prefix_width("\t- "), 6);
+        assert_eq!(prefix_width("🔥 "), 3);
+        assert_eq!(prefix_width("> > "), 4);
+    }
+
     #[test]
     fn test_count_special_chars() {
         assert_eq!(count_special_chars("hello world"), 0); // space is whitespace, not special
@@ -237,6 +781,14 @@ mod tests {
         assert_eq!(count_special_chars("   "), 0); // whitespace only
     }
 
+    #[test]
+    fn test_looks_like_code() {
+        assert!(looks_like_code("x = foo(bar[0]);"));
+        assert!(looks_like_code("} 50% {"));
+        assert!(!looks_like_code("Fix the parser (again)."));
+        assert!(!looks_like_code(""));
+    }
+
     #[test]
     fn test_is_footer_line() {
         assert!(is_footer_line("Signed-off-by: John Doe <john@example.com>"));
@@ -258,6 +810,96 @@ mod tests {
         assert!(!is_footer_line("Subject: this is not a footer"));
     }
 
+    #[test]
+    fn test_is_comment_line() {
+        assert!(is_comment_line("# Please enter the commit message", '#'));
+        assert!(is_comment_line("#", '#'));
+        assert!(!is_comment_line("// note", '#'));
+        assert!(!is_comment_line("Regular text", '#'));
+
+        assert!(is_comment_line("; Please enter the commit message", ';'));
+        assert!(!is_comment_line("## Heading", ';'));
+        assert!(!is_comment_line("#include <stdio.h>", ';'));
+        assert!(!is_comment_line("#123 fixed upstream", ';'));
+    }
+
+    #[test]
+    fn test_split_at_scissors() {
+        let input = "Subject\n\nBody\n# ------------------------ >8 ------------------------\ndiff --git a/x b/x\n";
+        let (message, tail) = split_at_scissors(input, '#');
+        assert_eq!(message, "Subject\n\nBody\n");
+        assert!(tail.starts_with("# ------------------------ >8"));
+        assert!(tail.ends_with("diff --git a/x b/x\n"));
+
+        // Scissors must use the configured comment character
+        let (message, tail) = split_at_scissors(input, ';');
+        assert_eq!(message, input);
+        assert_eq!(tail, "");
+
+        assert!(is_scissors_line(
+            "; ------------------------ >8 ------------------------",
+            ';'
+        ));
+        assert!(!is_scissors_line("# -------- >8 --------", '#'));
+    }
+
+    #[test]
+    fn test_fence_marker() {
+        assert_eq!(fence_marker("```"), Some(('`', 3)));
+        assert_eq!(fence_marker("```rust"), Some(('`', 3)));
+        assert_eq!(fence_marker("  ~~~~ text"), Some(('~', 4)));
+        assert_eq!(fence_marker("``"), None);
+        assert_eq!(fence_marker("```foo`bar"), None);
+        assert_eq!(fence_marker("text ```"), None);
+
+        assert!(is_closing_fence("```", '`', 3));
+        assert!(is_closing_fence("  `````  ", '`', 3));
+        assert!(!is_closing_fence("```rust", '`', 3));
+        assert!(!is_closing_fence("~~~", '`', 3));
+        assert!(!is_closing_fence("````", '`', 5));
+    }
+
+    #[test]
+    fn test_has_footer_tag() {
+        let tags = vec!["Change-Id".to_string(), "Ticket:".to_string()];
+        assert!(has_footer_tag("Change-Id: I1234abcd", &tags));
+        assert!(has_footer_tag("change-id: I1234abcd", &tags));
+        assert!(has_footer_tag("Ticket: ABC-42", &tags));
+        assert!(!has_footer_tag("Change-Ids: nope", &tags));
+        assert!(!has_footer_tag("Ticketing is hard", &tags));
+        assert!(!has_footer_tag("Change-Id: I1234abcd", &[]));
+    }
+
+    #[test]
+    fn test_trailer_token() {
+        assert_eq!(trailer_token("Change-Id: I1234", ":"), Some("Change-Id"));
+        assert_eq!(trailer_token("Bug: 42", ":"), Some("Bug"));
+        assert_eq!(
+            trailer_token("Link: https://example.com", ":"),
+            Some("Link")
+        );
+        assert_eq!(
+            trailer_token("BREAKING CHANGE: drops v1 API", ":"),
+            Some("BREAKING CHANGE")
+        );
+        assert_eq!(trailer_token("Cc : someone", ":"), Some("Cc"));
+        assert_eq!(trailer_token("Fixes #123", ":"), None);
+        assert_eq!(trailer_token("Fixes #123", ":#"), Some("Fixes"));
+
+        assert_eq!(trailer_token("This is prose: not a trailer", ":"), None);
+        assert_eq!(trailer_token("-x: nope", ":"), None);
+        assert_eq!(trailer_token("https://example.com", ":"), None);
+        assert_eq!(trailer_token("", ":"), None);
+    }
+
+    #[test]
+    fn test_is_listed_tag() {
+        let tags = vec!["EN".to_string(), "Ticket:".to_string()];
+        assert!(is_listed_tag("EN", &tags));
+        assert!(is_listed_tag("ticket", &tags));
+        assert!(!is_listed_tag("Bug", &tags));
+    }
+
     #[test]
     fn test_is_list_item() {
         // Bullet lists
@@ -277,6 +919,11 @@ mod tests {
         assert!(is_list_item("✅ Check bullet"));
         assert!(is_list_item("  🚀 Indented emoji"));
 
+        // Task lists
+        assert!(is_list_item("- [ ] todo"));
+        assert!(is_list_item("* [x] done"));
+        assert!(is_list_item("1. [X] numbered and done"));
+
         // Should not match non-list items
         assert!(!is_list_item("Regular text"));
         assert!(!is_list_item("*no space after asterisk"));
@@ -303,6 +950,40 @@ mod tests {
         // Edge cases
         assert_eq!(extract_bullet_prefix("*  Multiple spaces"), "*  ");
         assert_eq!(extract_bullet_prefix("1.   Extra spaces"), "1.   ");
+
+        // Task-list checkboxes belong to the marker
+        assert_eq!(extract_bullet_prefix("- [ ] todo"), "- [ ] ");
+        assert_eq!(extract_bullet_prefix("  * [x]  done"), "  * [x]  ");
+        assert_eq!(extract_bullet_prefix("2. [X] done"), "2. [X] ");
+        assert_eq!(extract_bullet_prefix("- [ ]"), "- [ ]");
+        assert_eq!(extract_bullet_prefix("- [link] text"), "- ");
+        assert_eq!(extract_bullet_prefix("- [x]y"), "- ");
+    }
+
+    #[test]
+    fn test_quote_prefix() {
+        assert_eq!(quote_prefix("> text"), "> ");
+        assert_eq!(quote_prefix("  >> text"), "  >> ");
+        assert_eq!(quote_prefix("> > text"), "> > ");
+        assert_eq!(quote_prefix(">"), ">");
+        assert_eq!(quote_prefix("> >= 3.0"), "> ");
+        assert_eq!(quote_prefix(">=3.0 is required"), "");
+        assert_eq!(quote_prefix(">>= shifts right"), "");
+        assert_eq!(quote_prefix(">text"), "");
+        assert_eq!(quote_prefix("text"), "");
+
+        assert!(is_quote_line(">> nested"));
+        assert!(!is_quote_line("->"));
+    }
+
+    #[test]
+    fn test_ordered_marker() {
+        assert_eq!(ordered_marker("1."), Some((1, '.')));
+        assert_eq!(ordered_marker("10)"), Some((10, ')')));
+        assert_eq!(ordered_marker("-"), None);
+        assert_eq!(ordered_marker("."), None);
+        assert_eq!(ordered_marker("1a."), None);
+        assert_eq!(ordered_marker("🔥"), None);
     }
 
     #[test]
@@ -0,0 +1,151 @@
+//! Content verification: make sure reflowing never loses or invents text.
+//!
+//! The PRD requires that rule72 never deletes input lines and at worst
+//! copies the message through unchanged. After pretty printing, the
+//! non-whitespace tokens of input and output are compared as multisets;
+//! wrapping and re-indenting only move whitespace, so any difference means
+//! the pipeline dropped or duplicated content. Rewrapping a quotation
+//! repeats its `>` prefix on every line, so each token is compared together
+//! with the quote depth of its line rather than counting the markers; a
+//! quote prefix that is added, dropped or changed still counts as a change.
+//! When lists are renumbered or bullets normalised (see
+//! [`Options::renumber_lists`] and [`Options::normalize_bullets`]), the
+//! affected list markers only count as markers; otherwise they must be kept
+//! exactly.
+
+use crate::types::Options;
+use crate::utils::{count_indent, is_cjk, is_list_item, ordered_marker, quote_depth, quote_prefix};
+
+/// Split text into the tokens compared by [`same_content`].
+///
+/// Tokens are whitespace-separated words, except that every CJK character
+/// is a token on its own, since CJK text may be wrapped between characters.
+pub fn content_tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    for word in text.split_whitespace() {
+        let mut start = 0;
+        for (pos, c) in word.char_indices() {
+            if is_cjk(c) {
+                if start < pos {
+                    tokens.push(&word[start..pos]);
+                }
+                tokens.push(&word[pos..pos + c.len_utf8()]);
+                start = pos + c.len_utf8();
+            }
+        }
+        if start < word.len() {
+            tokens.push(&word[start..]);
+        }
+    }
+    tokens
+}
+
+/// Tokens of every line paired with the quote depth of the line (lines
+/// indented four columns or more are code, not quotes), with the
+/// marker of a list item replaced by a placeholder (`0.` / `0)` or `-`) if
+/// `opts` allows the formatter to change it
+fn line_tokens<'a>(text: &'a str, opts: &Options) -> Vec<(usize, &'a str)> {
+    let mut tokens = Vec::new();
+    for line in text.lines() {
+        let prefix = if count_indent(line) < 4 {
+            quote_prefix(line)
+        } else {
+            ""
+        };
+        let depth = quote_depth(prefix);
+        let content = &line[prefix.len()..];
+        let start = tokens.len();
+        tokens.extend(
+            content_tokens(content)
+                .into_iter()
+                .map(|token| (depth, token)),
+        );
+        if is_list_item(content.trim()) {
+            tokens[start].1 = match ordered_marker(tokens[start].1) {
+                Some((_, '.')) if opts.renumber_lists => "0.",
+                Some((_, ')')) if opts.renumber_lists => "0)",
+                None if opts.normalize_bullets && matches!(tokens[start].1, "-" | "*") => "-",
+                _ => tokens[start].1,
+            };
+        }
+    }
+    tokens
+}
+
+/// Check that `output` contains exactly the same tokens as `input`, at the
+/// same quote depth, regardless of order, whitespace, the spelling of quote
+/// prefixes (`>>` or `> >`) and, if enabled in `opts`, list numbering and
+/// bullet characters.
+pub fn same_content(input: &str, output: &str, opts: &Options) -> bool {
+    let mut expected = line_tokens(input, opts);
+    let mut actual = line_tokens(output, opts);
+    if expected.len() != actual.len() {
+        return false;
+    }
+    expected.sort_unstable();
+    actual.sort_unstable();
+    expected == actual
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_tokens() {
+        assert_eq!(content_tokens("  hello\n world  "), vec!["hello", "world"]);
+        assert_eq!(
+            content_tokens("Rustで書く test"),
+            vec!["Rust", "で", "書", "く", "test"]
+        );
+    }
+
+    #[test]
+    fn test_same_content() {
+        let opts = Options::default();
+        let same = |input, output| same_content(input, output, &opts);
+        assert!(same("A long line of text", "A long\nline of\ntext\n"));
+        assert!(same("日本語の文章", "日本語の\n文章"));
+        assert!(same("- item", "  - item"));
+        assert!(same("> one two", "> one\n> two"));
+        assert!(same(">> one two", "> > one\n> > two"));
+
+        assert!(!same("one two three", "one three"));
+        assert!(!same("one two", "one two two"));
+        assert!(!same("one two", "one too"));
+        assert!(!same("> one two", "> one\n>"));
+        assert!(!same("1. one\n2. two", "1. one two"));
+        assert!(!same(">=3.0 is required now", ">=3.0 is\n>required now"));
+        assert!(!same("> one two", "> one\ntwo"));
+        assert!(!same("> one two", ">> one two"));
+    }
+
+    #[test]
+    fn test_same_content_list_markers() {
+        let input = "3. one\n3) two\n* three";
+        let output = "3. one\n4) two\n- three";
+        assert!(!same_content(input, output, &Options::default()));
+
+        let renumber = Options {
+            renumber_lists: true,
+            ..Options::default()
+        };
+        assert!(same_content(input, "3. one\n4) two\n* three", &renumber));
+        assert!(!same_content(input, output, &renumber));
+        assert!(!same_content("1. one", "1) one", &renumber));
+
+        let normalize = Options {
+            normalize_bullets: true,
+            ..Options::default()
+        };
+        assert!(same_content(input, "3. one\n3) two\n- three", &normalize));
+        assert!(!same_content(input, output, &normalize));
+
+        let both = Options {
+            renumber_lists: true,
+            normalize_bullets: true,
+            ..Options::default()
+        };
+        assert!(same_content(input, output, &both));
+    }
+}
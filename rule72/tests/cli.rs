@@ -1,6 +1,6 @@
 use assert_cmd::prelude::*;
-use predicates::prelude::*;
 use std::io::Write;
+use std::path::Path;
 use std::process::{Command, Stdio};
 
 #[test]
@@ -18,7 +18,9 @@ fn test_width_arg() -> Result<(), Box<dyn std::error::Error>> {
 
     let output = child.wait_with_output()?;
     assert!(output.status.success());
-    assert!(String::from_utf8_lossy(&output.stdout).contains("This is a test of the width argument."));
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("This is a test of the width argument.")
+    );
 
     Ok(())
 }
@@ -37,7 +39,6 @@ fn test_division_by_zero_cli() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-
 #[test]
 fn test_headline_width_arg() -> Result<(), Box<dyn std::error::Error>> {
     let mut cmd = Command::cargo_bin("rule72")?;
@@ -69,10 +70,645 @@ fn test_simple_reflow() -> Result<(), Box<dyn std::error::Error>> {
 
     let output = child.wait_with_output()?;
     assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("This is a long line that should be wrapped by the tool."));
+
+    Ok(())
+}
+
+#[test]
+fn test_check_clean_message() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("rule72")?;
+    let mut child = cmd
+        .arg("--check")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let child_stdin = child.stdin.as_mut().unwrap();
+    child_stdin.write_all(b"Subject\n\nShort body.\n")?;
+
+    let output = child.wait_with_output()?;
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_check_needs_reflow() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("rule72")?;
+    let mut child = cmd
+        .arg("--check")
+        .arg("--width")
+        .arg("30")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let child_stdin = child.stdin.as_mut().unwrap();
+    child_stdin.write_all(b"Subject\n\nThis body line is longer than thirty columns.\n")?;
+
+    let output = child.wait_with_output()?;
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--- original"));
+    assert!(stderr.contains("+This body line is longer than"));
+
+    Ok(())
+}
+
+/// Run git in `dir` with a fixed identity, panicking on failure.
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(["-c", "commit.gpgsign=false"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("failed to run git");
+    assert!(output.status.success(), "git {args:?} failed");
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Create an empty commit in `dir` with exactly the given message.
+fn commit(dir: &Path, message: &str) -> String {
+    let msg_path = dir.join(".git").join("TEST_MSG");
+    std::fs::write(&msg_path, message).unwrap();
+    git(
+        dir,
+        &[
+            "commit",
+            "-q",
+            "--allow-empty",
+            "--cleanup=verbatim",
+            "-F",
+            msg_path.to_str().unwrap(),
+        ],
+    );
+    git(dir, &["rev-parse", "HEAD"])
+}
+
+#[test]
+fn test_check_range() -> Result<(), Box<dyn std::error::Error>> {
+    let repo = tempfile::tempdir()?;
+    git(repo.path(), &["init", "-q"]);
+    commit(repo.path(), "Initial commit\n");
+    let base = git(repo.path(), &["rev-parse", "HEAD"]);
+    commit(repo.path(), "Clean commit\n\nShort body.\n");
+    let bad = commit(
+        repo.path(),
+        "Messy commit\n\nThis body line is much longer than seventy-two columns and needs to be wrapped.\n",
+    );
+
+    let output = Command::cargo_bin("rule72")?
+        .arg("check")
+        .arg(format!("{base}..HEAD"))
+        .current_dir(repo.path())
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(3));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("{bad} Messy commit")));
+    assert!(!stdout.contains("Clean commit"));
+    assert!(stdout.contains("+This body line is much longer"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 of 2 commit(s)"));
+
+    Ok(())
+}
+
+#[test]
+fn test_check_range_clean() -> Result<(), Box<dyn std::error::Error>> {
+    let repo = tempfile::tempdir()?;
+    git(repo.path(), &["init", "-q"]);
+    commit(repo.path(), "Initial commit\n");
+    commit(repo.path(), "Clean commit\n\nShort body.\n");
+
+    let output = Command::cargo_bin("rule72")?
+        .arg("check")
+        .arg("HEAD~1..HEAD")
+        .current_dir(repo.path())
+        .output()?;
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_check_range_invalid() -> Result<(), Box<dyn std::error::Error>> {
+    let repo = tempfile::tempdir()?;
+    git(repo.path(), &["init", "-q"]);
+
+    let output = Command::cargo_bin("rule72")?
+        .arg("check")
+        .arg("no-such-branch..HEAD")
+        .current_dir(repo.path())
+        .output()?;
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("git rev-list"));
+
+    // A range is never passed to git as an option
+    commit(repo.path(), "Messy commit\n\nThis body line is much longer than seventy-two columns and needs to be wrapped.\n");
+    let output = Command::cargo_bin("rule72")?
+        .args(["check", "--", "--all"])
+        .current_dir(repo.path())
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_in_place_multiple_files() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let messy = dir.path().join("messy.txt");
+    let clean = dir.path().join("clean.txt");
+    std::fs::write(
+        &messy,
+        "Subject\n\nThis body line is much longer than seventy-two columns and needs to be wrapped.\n",
+    )?;
+    std::fs::write(&clean, "Subject\n\nShort body.\n")?;
+
+    let output = Command::cargo_bin("rule72")?
+        .arg("-i")
+        .arg(&messy)
+        .arg(&clean)
+        .output()?;
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        std::fs::read_to_string(&messy)?,
+        "Subject\n\nThis body line is much longer than seventy-two columns and needs to be\nwrapped.\n"
+    );
+    assert_eq!(std::fs::read_to_string(&clean)?, "Subject\n\nShort body.\n");
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_in_place_file_named_like_subcommand() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    std::fs::write(
+        dir.path().join("check"),
+        "Subject\n\nThis body line is much longer than seventy-two columns and needs to be wrapped.\n",
+    )?;
+
+    // `--` ends the options, so `check` is a FILE rather than the subcommand
+    let output = Command::cargo_bin("rule72")?
+        .args(["-i", "--", "check"])
+        .current_dir(dir.path())
+        .output()?;
+
+    assert!(output.status.success());
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("check"))?,
+        "Subject\n\nThis body line is much longer than seventy-two columns and needs to be\nwrapped.\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_in_place_preserves_permissions() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("COMMIT_EDITMSG");
+    std::fs::write(
+        &path,
+        "Subject\n\nThis body line is much longer than seventy-two columns and needs to be wrapped.\n",
+    )?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640))?;
+
+    let output = Command::cargo_bin("rule72")?
+        .arg("--in-place")
+        .arg(&path)
+        .output()?;
+
+    assert!(output.status.success());
+    let mode = std::fs::metadata(&path)?.permissions().mode();
+    assert_eq!(mode & 0o777, 0o640);
+    assert!(std::fs::read_to_string(&path)?.contains("needs to be\nwrapped."));
+
+    Ok(())
+}
+
+#[test]
+fn test_files_to_stdout_and_check() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("msg.txt");
+    let original = "Subject\n\nThis body line is longer than thirty columns.\n";
+    std::fs::write(&path, original)?;
+
+    let output = Command::cargo_bin("rule72")?
+        .args(["--width", "30"])
+        .arg(&path)
+        .output()?;
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("This body line is longer than\nthirty columns."));
+
+    let output = Command::cargo_bin("rule72")?
+        .args(["--width", "30", "--check"])
+        .arg(&path)
+        .output()?;
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains(&format!("--- {}", path.display())));
+
+    // Neither mode touches the file
+    assert_eq!(std::fs::read_to_string(&path)?, original);
+
+    Ok(())
+}
+
+#[test]
+fn test_in_place_requires_file() -> Result<(), Box<dyn std::error::Error>> {
+    let output = Command::cargo_bin("rule72")?
+        .arg("--in-place")
+        .stdin(Stdio::null())
+        .output()?;
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("requires at least one FILE"));
+
+    Ok(())
+}
+
+const LONG_HEADING: &str =
+    "# This hash-prefixed line is content and longer than seventy-two columns wide";
+
+#[test]
+fn test_comment_char_from_git_config_in_hook() -> Result<(), Box<dyn std::error::Error>> {
+    let repo = tempfile::tempdir()?;
+    git(repo.path(), &["init", "-q"]);
+    git(repo.path(), &["config", "core.commentChar", ";"]);
+    let input = format!("Subject\n\n{LONG_HEADING}\n; A comment line that is also longer than seventy-two columns wide\n");
+
+    // Run as a hook: the configured comment char applies, `#` lines are content
+    let mut child = Command::cargo_bin("rule72")?
+        .env("GIT_INDEX_FILE", repo.path().join(".git/index"))
+        .current_dir(repo.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    child.stdin.as_mut().unwrap().write_all(input.as_bytes())?;
+    let output = child.wait_with_output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains(LONG_HEADING));
+    assert!(stdout.contains("; A comment line that is also longer than seventy-two columns wide\n"));
+
+    // Outside a hook the default `#` applies
+    let mut child = Command::cargo_bin("rule72")?
+        .env_remove("GIT_DIR")
+        .env_remove("GIT_INDEX_FILE")
+        .current_dir(repo.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    child.stdin.as_mut().unwrap().write_all(input.as_bytes())?;
+    let output = child.wait_with_output()?;
+    assert!(String::from_utf8_lossy(&output.stdout).contains(LONG_HEADING));
+
+    Ok(())
+}
+
+#[test]
+fn test_comment_char_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let mut child = Command::cargo_bin("rule72")?
+        .args(["--comment-char", ";"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .as_mut()
+        .unwrap()
+        .write_all(format!("Subject\n\n{LONG_HEADING}\n").as_bytes())?;
+    let output = child.wait_with_output()?;
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains(LONG_HEADING));
+
+    let output = Command::cargo_bin("rule72")?
+        .args(["--comment-char", "ab"])
+        .stdin(Stdio::null())
+        .output()?;
+    assert!(!output.status.success());
+
+    Ok(())
+}
+
+/// Run rule72 in `dir` with `args`, feeding `input` on stdin.
+fn run_in(dir: &Path, args: &[&str], input: &str) -> std::process::Output {
+    let mut child = Command::cargo_bin("rule72")
+        .unwrap()
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Usage errors exit before reading stdin, so the pipe may be closed
+    let _ = child.stdin.as_mut().unwrap().write_all(input.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_config_precedence() -> Result<(), Box<dyn std::error::Error>> {
+    let repo = tempfile::tempdir()?;
+    git(repo.path(), &["init", "-q"]);
+    let subdir = repo.path().join("src");
+    std::fs::create_dir(&subdir)?;
+    std::fs::write(
+        repo.path().join(".rule72.toml"),
+        "width = 30\nfooter-tags = [\"Ticket\"]\n",
+    )?;
+    let input = "Subject\n\nalpha beta gamma delta epsilon zeta eta theta iota kappa lambda mu nu xi omicron\n\nTicket: ABC-123 with a value that is long enough to be wrapped if it were prose\n";
+
+    // .rule72.toml is found from a subdirectory
+    let output = run_in(&subdir, &[], input);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("alpha beta gamma delta epsilon\n"));
+    assert!(stdout.contains(
+        "Ticket: ABC-123 with a value that is long enough to be wrapped if it were prose\n"
+    ));
+
+    // rule72.* git config overrides the file
+    git(repo.path(), &["config", "rule72.width", "40"]);
+    let output = run_in(&subdir, &[], input);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("alpha beta gamma delta epsilon zeta eta\n"));
+
+    // CLI flags override both
+    let output = run_in(&subdir, &["--width", "50"], input);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("alpha beta gamma delta epsilon zeta eta theta iota\n"));
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_config_file() -> Result<(), Box<dyn std::error::Error>> {
+    let repo = tempfile::tempdir()?;
+    git(repo.path(), &["init", "-q"]);
+    std::fs::write(repo.path().join(".rule72.toml"), "widht = 30\n")?;
+
+    let output = run_in(repo.path(), &[], "Subject\n");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains(".rule72.toml"));
+
+    Ok(())
+}
+
+#[test]
+fn test_trailer_flags() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let input = "Subject\n\nBody\n\nEN: this last paragraph looks like a trailer but it is really just some prose\n";
+
+    let output = run_in(dir.path(), &[], input);
+    assert!(String::from_utf8_lossy(&output.stdout).contains(
+        "EN: this last paragraph looks like a trailer but it is really just some prose\n"
+    ));
+
+    let output = run_in(dir.path(), &["--footer-deny", "EN"], input);
+    assert!(String::from_utf8_lossy(&output.stdout).contains(
+        "EN: this last paragraph looks like a trailer but it is really just some\nprose\n"
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_lint_subcommand() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+
+    let output = run_in(
+        dir.path(),
+        &["lint"],
+        "Subject line with a period.\nBody directly below  \n",
+    );
+    assert_eq!(output.status.code(), Some(3));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("<stdin>:1: warning[headline-period]: headline ends with a period\n"));
+    assert!(stdout.contains("<stdin>:2: error[headline-blank-line]:"));
+    assert!(stdout.contains("<stdin>:2: warning[trailing-whitespace]:"));
+
+    let path = dir.path().join("msg.txt");
+    std::fs::write(&path, "Subject\n\nClean body.\n")?;
+    let output = Command::cargo_bin("rule72")?
+        .arg("lint")
+        .arg(&path)
+        .output()?;
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_lint_conventional() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let input = "feat(ui)!: drop the legacy theme\n\nThe old theme is gone.\n";
+
+    // Not validated unless asked for
+    let output = run_in(dir.path(), &["lint"], input);
+    assert!(output.status.success());
+
+    let output = run_in(
+        dir.path(),
+        &["lint", "--conventional", "--conventional-scope", "api"],
+        input,
+    );
+    assert_eq!(output.status.code(), Some(3));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("<stdin>:1: error[conventional-scope]: scope `ui` is not one of: api\n")
+    );
+    assert!(stdout.contains("<stdin>:1: error[conventional-breaking]:"));
+
+    // The footer value may run on over unindented lines
+    let input = "feat!: drop the v1 API\n\n\
+                 The old endpoints are gone.\n\n\
+                 BREAKING CHANGE: clients still calling /v1 must move to /v2\n\
+                 before upgrading the server.\n";
+    let output = run_in(dir.path(), &["lint", "--conventional"], input);
     assert!(
+        output.status.success(),
+        "{}",
         String::from_utf8_lossy(&output.stdout)
-            .contains("This is a long line that should be wrapped by the tool.")
     );
 
     Ok(())
 }
+
+#[test]
+fn test_wrap_optimal() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let input = "Subject\n\naaa bb cc ddddd\n";
+
+    let output = run_in(dir.path(), &["--width", "6"], input);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Subject\n\naaa bb\ncc\nddddd\n"
+    );
+
+    let output = run_in(dir.path(), &["--width", "6", "--wrap", "optimal"], input);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Subject\n\naaa\nbb cc\nddddd\n"
+    );
+
+    let output = run_in(dir.path(), &["--wrap", "balanced"], input);
+    assert_eq!(output.status.code(), Some(2));
+
+    Ok(())
+}
+
+#[test]
+fn test_list_marker_flags() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let input = "Subject\n\n1. one\n1. two\n\n* three\n- four\n";
+
+    let output = run_in(dir.path(), &[], input);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), input);
+
+    let output = run_in(
+        dir.path(),
+        &["--renumber-lists", "--normalize-bullets"],
+        input,
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Subject\n\n1. one\n2. two\n\n* three\n* four\n"
+    );
+
+    // Blocks under an item whose marker grows stay lined up with its text
+    let input = "Subject\n\n8. h\n8. i\n8. j\n\n   - nested under j\n\n   para of j\n\n       code under j\n";
+    let output = run_in(dir.path(), &["--renumber-lists"], input);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Subject\n\n8. h\n9. i\n10. j\n\n    - nested under j\n\n    para of j\n\n        code under j\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_reflow_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let input = "Subject\n\nThis first line was edited and is now longer than the width.\nThe second line\nwas broken by hand.\n";
+
+    let output = run_in(dir.path(), &["--width", "40", "--reflow", "minimal"], input);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Subject\n\nThis first line was edited and is now\nlonger than the width. The second line\nwas broken by hand.\n"
+    );
+
+    let output = run_in(dir.path(), &["--width", "40", "--reflow", "all"], input);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Subject\n\nThis first line was edited and is now\nlonger than the width. The second line\nwas broken by hand.\n"
+    );
+    let output = run_in(dir.path(), &["--reflow", "all"], input);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Subject\n\nThis first line was edited and is now longer than the width. The second\nline was broken by hand.\n"
+    );
+
+    let output = run_in(dir.path(), &["--reflow", "some"], input);
+    assert!(!output.status.success());
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_emit_json() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let output = run_in(
+        dir.path(),
+        &["--emit", "json"],
+        "Subject\n\n- item\n\nSigned-off-by: X <x@y>\n",
+    );
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.starts_with(r#"{"headline":{"text":"Subject","line_number":0,"indent":0,"#));
+    assert!(stdout
+        .contains(r#"{"kind":"list","introduction":[],"items":[{"bullet_line":{"text":"- item""#));
+    assert!(stdout.contains(r#""final_category":"Footer""#));
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_options_passthrough() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let input = "Subject\n\nA body line that would otherwise be wrapped at a narrow width.\n";
+
+    // stdin: copied through verbatim with exit code 1
+    let output = run_in(dir.path(), &["--width", "0"], input);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), input);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid options"));
+
+    // --in-place: the file is left untouched
+    let path = dir.path().join("msg.txt");
+    std::fs::write(&path, input)?;
+    let output = Command::cargo_bin("rule72")?
+        .args(["--width", "0", "--in-place"])
+        .arg(&path)
+        .output()?;
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(std::fs::read_to_string(&path)?, input);
+
+    // --check cannot pass through, so it fails outright
+    let output = run_in(dir.path(), &["--width", "0", "--check"], input);
+    assert_eq!(output.status.code(), Some(1));
+
+    Ok(())
+}
+
+#[test]
+fn test_verify_idempotent() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let input = "Subject\n\n- keep the trailers that the old hook dropped, like Signed-off-by:\n";
+
+    // Stable output is printed as usual
+    let plain = run_in(dir.path(), &["--width", "30"], input);
+    let output = run_in(dir.path(), &["--width", "30", "--verify-idempotent"], input);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, plain.stdout);
+    assert!(output.stderr.is_empty());
+
+    // The flag also works in place
+    let path = dir.path().join("msg.txt");
+    std::fs::write(&path, input)?;
+    let output = Command::cargo_bin("rule72")?
+        .args(["--width", "30", "--verify-idempotent", "--in-place"])
+        .arg(&path)
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(std::fs::read(&path)?, plain.stdout);
+
+    // It is a reflow option, not a check mode
+    let output = run_in(dir.path(), &["--verify-idempotent", "--check"], input);
+    assert_eq!(output.status.code(), Some(2));
+
+    Ok(())
+}
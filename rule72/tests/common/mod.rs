@@ -0,0 +1,24 @@
+//! Helpers shared by the corpus-driven integration tests.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The `data/` corpus of real-world and synthetic commit messages
+pub fn data_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../data")
+}
+
+/// All `*.txt` files below `dir`, sorted for stable failure output
+pub fn corpus_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).expect("corpus directory is readable") {
+        let path = entry.expect("corpus entry is readable").path();
+        if path.is_dir() {
+            files.extend(corpus_files(&path));
+        } else if path.extension().is_some_and(|ext| ext == "txt") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
@@ -0,0 +1,54 @@
+//! Golden test: every message under `data/` must reflow to the matching file
+//! under `data.out/`.
+//!
+//! After an intentional formatting change, regenerate the expectations with
+//! `RULE72_BLESS=1 cargo test --test corpus` and review the `data.out/` diff.
+
+mod common;
+
+use rule72::check::unified_diff;
+use rule72::{reflow, Options};
+use std::env;
+use std::fs;
+
+#[test]
+fn test_corpus_matches_expected_output() {
+    let data = common::data_dir();
+    let expected_dir = data.join("../data.out");
+    let bless = env::var_os("RULE72_BLESS").is_some_and(|value| value == "1");
+    let opts = Options::default();
+
+    let files = common::corpus_files(&data);
+    assert!(!files.is_empty(), "no corpus files in {}", data.display());
+
+    let mut failures = Vec::new();
+    for path in &files {
+        let relative = path
+            .strip_prefix(&data)
+            .expect("corpus file is below data/");
+        let expected_path = expected_dir.join(relative);
+        let input = fs::read_to_string(path).expect("corpus file is UTF-8");
+        let actual = reflow(&input, &opts);
+
+        if bless {
+            fs::create_dir_all(expected_path.parent().expect("file has a parent"))
+                .expect("data.out/ is writable");
+            fs::write(&expected_path, &actual).expect("data.out/ is writable");
+            continue;
+        }
+
+        let expected = fs::read_to_string(&expected_path).unwrap_or_default();
+        if actual != expected {
+            let name = relative.display().to_string();
+            failures.push(unified_diff(&expected, &actual, &name, &name));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} corpus files differ from data.out/ (rerun with RULE72_BLESS=1 to update):\n{}",
+        failures.len(),
+        files.len(),
+        failures.concat()
+    );
+}
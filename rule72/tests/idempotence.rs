@@ -0,0 +1,81 @@
+//! Reflowing must be idempotent: formatting already formatted output is a
+//! no-op. Checked over the `data/` corpus and over messages that used to
+//! change on the second pass.
+
+mod common;
+
+use rule72::{reflow, Options, ReflowMode, WrapAlgorithm};
+use std::fs;
+
+const WIDTHS: [usize; 4] = [30, 50, 72, 100];
+
+fn assert_idempotent(name: &str, input: &str) {
+    for width in WIDTHS {
+        for wrap in [WrapAlgorithm::Greedy, WrapAlgorithm::Optimal] {
+            for mode in [ReflowMode::Overflow, ReflowMode::Minimal, ReflowMode::All] {
+                let opts = Options {
+                    width,
+                    wrap,
+                    reflow: mode,
+                    ..Options::default()
+                };
+                let once = reflow(input, &opts);
+                let twice = reflow(&once, &opts);
+                assert_eq!(
+                    once, twice,
+                    "{name}: second pass differs at width {width} with {wrap:?} wrapping and {mode:?} reflow"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_corpus_is_idempotent() {
+    let data = common::data_dir();
+    let files = common::corpus_files(&data);
+    assert!(!files.is_empty(), "no corpus files in {}", data.display());
+
+    for path in files {
+        let input = fs::read_to_string(&path).expect("corpus file is UTF-8");
+        assert_idempotent(&path.display().to_string(), &input);
+    }
+}
+
+#[test]
+fn test_wrapped_trailer_tag_is_idempotent() {
+    // At width 30 the tag used to start a continuation line, which the second
+    // pass read as a trailer block and separated with a blank line
+    assert_idempotent(
+        "trailer tag in list item",
+        "Subject\n\n- keep the trailers that the old hook dropped, like Signed-off-by:\n",
+    );
+}
+
+#[test]
+fn test_list_continuations_are_idempotent() {
+    assert_idempotent(
+        "url continuation",
+        "Subject\n\n- read the design notes first:\n  https://example.com/some/really/long/path/to/the/design/notes then continue\n",
+    );
+    assert_idempotent(
+        "tab indented bullet",
+        "Subject\n\n\t- the tab indented bullet drains the queue before it returns\n",
+    );
+}
+
+#[test]
+fn test_quotes_are_idempotent() {
+    assert_idempotent(
+        "nested quote",
+        "Subject\n\n> The reviewer wrote that this should be split into two commits, one per crate\n>\n>> and an older reply quoted inside it that runs well past any reasonable width\n",
+    );
+}
+
+#[test]
+fn test_list_item_paragraphs_are_idempotent() {
+    assert_idempotent(
+        "multi-paragraph item",
+        "Subject\n\n- Split the scheduler into a planner and an executor so they can be tested apart\n\n  The planner now owns the queue, and the executor only sees the tasks handed to it by the planner\n\n      planner.plan(&queue)\n  - nested item\n\n    with a second paragraph that is long enough to need wrapping at every width\n- Next item\n",
+    );
+}
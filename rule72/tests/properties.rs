@@ -0,0 +1,297 @@
+//! Property-based tests of the whole pipeline: `lex_lines` →
+//! `classify_with_context` → `build_document` → `pretty_print`.
+//!
+//! Structured commit messages (headline, paragraphs, nested lists, code,
+//! tables, quotes, trailers, emoji and CJK text) are generated and the
+//! formatter must not panic, lose or invent tokens, produce lines over the
+//! width other than a single unbreakable token, or change its own output.
+//! The pipeline is called directly, so the content verification in
+//! [`rule72::reflow`] cannot hide a bug by falling back to the input.
+//! Arbitrary text and raw lines starting with quote, comment, list and
+//! number markers are checked the same way, except for the width.
+//! Set `PROPTEST_CASES` to run more cases.
+
+use proptest::prelude::*;
+use rule72::utils::{display_width, extract_bullet_prefix, is_list_item, quote_prefix};
+use rule72::verify::same_content;
+use rule72::{parse_document, pretty_print, try_reflow, Options, ReflowMode, WrapAlgorithm};
+
+/// Run the pipeline without the verification fallback of `reflow`
+fn format(input: &str, opts: &Options) -> String {
+    pretty_print(&parse_document(input, opts), opts)
+}
+
+/// A prose word that can never start a list item, footer, quote or other
+/// block when wrapping moves it to the start of a line
+fn word() -> impl Strategy<Value = String> {
+    prop_oneof![
+        6 => "[a-z]{1,9}",
+        2 => "[A-Z][a-z]{1,8}[,.]?",
+        1 => ("[a-z]{2,6}", prop::sample::select(vec!["🚀", "✅", "🔥", "🐛"]))
+            .prop_map(|(word, emoji)| format!("{word}{emoji}")),
+        // CJK runs end in punctuation, so no break leaves a lone character
+        // followed by a space at the start of a line
+        1 => "[\u{4e00}-\u{4e80}\u{3041}-\u{3093}]{1,12}".prop_map(|run| format!("{run}。")),
+        1 => "[a-z]{12,40}",
+        1 => "[a-z]{3,12}".prop_map(|path| format!("https://example.com/{path}")),
+    ]
+}
+
+/// Words split into lines of random length
+fn prose_lines(max_words: usize) -> impl Strategy<Value = Vec<String>> {
+    (
+        prop::collection::vec(word(), 1..max_words),
+        prop::collection::vec(1usize..12, 1..8),
+    )
+        .prop_map(|(words, breaks)| {
+            let mut lines = Vec::new();
+            let mut rest = words.as_slice();
+            for len in breaks.into_iter().cycle() {
+                if rest.is_empty() {
+                    break;
+                }
+                let (line, tail) = rest.split_at(len.min(rest.len()));
+                lines.push(line.join(" "));
+                rest = tail;
+            }
+            lines
+        })
+}
+
+fn paragraph() -> impl Strategy<Value = String> {
+    prose_lines(40).prop_map(|lines| lines.join("\n"))
+}
+
+/// A paragraph with a first-line and a hanging indent of up to three columns
+fn indented_paragraph() -> impl Strategy<Value = String> {
+    (prose_lines(40), 0usize..4, 0usize..4).prop_map(|(lines, first, hanging)| {
+        lines
+            .iter()
+            .enumerate()
+            .map(|(idx, line)| {
+                let indent = if idx == 0 { first } else { hanging };
+                format!("{}{line}", " ".repeat(indent))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
+
+fn bullet() -> impl Strategy<Value = String> {
+    prop::sample::select(vec!["-", "*", "1.", "2)", "10.", "- [ ]", "* [x]", "🔥"])
+        .prop_map(str::to_string)
+}
+
+/// A list with up to three levels of nesting. Continuation lines and
+/// further paragraphs of an item are aligned with the item text.
+fn list() -> impl Strategy<Value = String> {
+    let item = (
+        bullet(),
+        prose_lines(20),
+        0usize..3,
+        prop::option::weighted(0.2, prose_lines(20)),
+    );
+    prop::collection::vec(item, 1..6).prop_map(|items| {
+        let mut lines = Vec::new();
+        let mut depth = 0;
+        for (marker, text, requested, paragraph) in items {
+            // Nest at most one level deeper than the previous item
+            depth = requested.min(depth + 1);
+            let indent = " ".repeat(depth * 2);
+            let prefix = format!("{indent}{marker} ");
+            let padding = " ".repeat(display_width(&prefix));
+            for (idx, line) in text.iter().enumerate() {
+                if idx == 0 {
+                    lines.push(format!("{prefix}{line}"));
+                } else {
+                    lines.push(format!("{padding}{line}"));
+                }
+            }
+            if let Some(paragraph) = paragraph {
+                lines.push(String::new());
+                lines.extend(paragraph.iter().map(|line| format!("{padding}{line}")));
+            }
+        }
+        lines.join("\n")
+    })
+}
+
+fn code() -> impl Strategy<Value = String> {
+    prop_oneof![
+        prop::collection::vec("[a-z_]{1,8} = [0-9]{1,4};", 1..4)
+            .prop_map(|lines| format!("```rust\n{}\n```", lines.join("\n"))),
+        prop::collection::vec("[a-z_]{1,8}\\(\\);", 1..4).prop_map(|lines| lines
+            .iter()
+            .map(|line| format!("    {line}"))
+            .collect::<Vec<_>>()
+            .join("\n")),
+    ]
+}
+
+fn table() -> impl Strategy<Value = String> {
+    prop::collection::vec(("[a-z]{1,6}", "[0-9]{1,4}"), 1..4).prop_map(|rows| {
+        rows.iter()
+            .map(|(name, value)| format!("| {name} | {value} |"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
+
+fn quote() -> impl Strategy<Value = String> {
+    (
+        prop::sample::select(vec!["> ", ">> ", "> > "]),
+        prose_lines(20),
+    )
+        .prop_map(|(prefix, lines)| {
+            lines
+                .iter()
+                .map(|line| format!("{prefix}{line}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+}
+
+fn block() -> impl Strategy<Value = String> {
+    prop_oneof![
+        4 => paragraph(),
+        1 => indented_paragraph(),
+        3 => list(),
+        1 => code(),
+        1 => table(),
+        1 => quote(),
+    ]
+}
+
+fn trailers() -> impl Strategy<Value = Vec<String>> {
+    prop::collection::vec(
+        (
+            prop::sample::select(vec!["Signed-off-by", "Reviewed-by", "Fixes"]),
+            "[a-z]{1,8}",
+        )
+            .prop_map(|(tag, value)| format!("{tag}: {value}")),
+        0..3,
+    )
+}
+
+/// A commit message: short headline, blank-line separated body blocks and
+/// an optional trailer block
+fn message() -> impl Strategy<Value = String> {
+    (
+        prop::collection::vec("[a-z]{1,6}", 1..4),
+        prop::collection::vec(block(), 0..6),
+        trailers(),
+    )
+        .prop_map(|(headline, blocks, trailers)| {
+            let mut parts = vec![headline.join(" ")];
+            parts.extend(blocks);
+            if !trailers.is_empty() {
+                parts.push(trailers.join("\n"));
+            }
+            parts.join("\n\n") + "\n"
+        })
+}
+
+/// A line as it may be typed by hand, starting with whatever marker
+fn raw_line() -> impl Strategy<Value = String> {
+    (
+        prop::sample::select(vec!["", " ", "  ", "    ", "\t"]),
+        prop::sample::select(vec![
+            "", ">", "> ", ">>", "> > ", ">=", ">>=", "#", "# ", "#123", ";", "; ", "-", "- ",
+            "--", "* ", "+ ", "1.", "1. ", "2) ", "10. ", "3.5", "42", "|", "- [ ] ", "```",
+        ]),
+        prop::collection::vec(word(), 0..12),
+    )
+        .prop_map(|(indent, marker, words)| format!("{indent}{marker}{}", words.join(" ")))
+}
+
+/// Raw lines and blank lines in any order
+fn raw_message() -> impl Strategy<Value = String> {
+    prop::collection::vec(
+        prop_oneof![4 => raw_line(), 1 => Just(String::new())],
+        0..20,
+    )
+    .prop_map(|lines| lines.join("\n") + "\n")
+}
+
+fn options() -> impl Strategy<Value = Options> {
+    (
+        30usize..100,
+        prop::sample::select(vec![WrapAlgorithm::Greedy, WrapAlgorithm::Optimal]),
+        prop::sample::select(vec![
+            ReflowMode::Overflow,
+            ReflowMode::Minimal,
+            ReflowMode::All,
+        ]),
+        any::<bool>(),
+        any::<bool>(),
+        prop::sample::select(vec!['#', ';']),
+    )
+        .prop_map(
+            |(width, wrap, reflow, renumber_lists, normalize_bullets, comment_char)| Options {
+                width,
+                wrap,
+                reflow,
+                renumber_lists,
+                normalize_bullets,
+                comment_char,
+                ..Options::default()
+            },
+        )
+}
+
+/// The text of an output line without its indentation, quote prefix and
+/// list marker
+fn line_content(line: &str) -> &str {
+    let line = &line[quote_prefix(line).len()..];
+    let line = if is_list_item(line) {
+        &line[extract_bullet_prefix(line).len()..]
+    } else {
+        line
+    };
+    line.trim()
+}
+
+proptest! {
+    #[test]
+    fn formatting_keeps_every_token(input in message(), opts in options()) {
+        let output = format(&input, &opts);
+        prop_assert!(same_content(&input, &output, &opts), "output:\n{}", output);
+    }
+
+    #[test]
+    fn formatted_lines_fit_the_width(input in message(), opts in options()) {
+        let output = format(&input, &opts);
+        for line in output.lines().skip(1) {
+            prop_assert!(
+                display_width(line) <= opts.width
+                    || line_content(line).split_whitespace().count() == 1,
+                "line over {} columns: {:?}\noutput:\n{}",
+                opts.width,
+                line,
+                output
+            );
+        }
+    }
+
+    #[test]
+    fn formatting_is_idempotent(input in message(), opts in options()) {
+        let once = format(&input, &opts);
+        let twice = format(&once, &opts);
+        prop_assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn arbitrary_input_never_fails(
+        input in prop_oneof!["(\\PC{0,20}[\n\t ]?){0,20}", raw_message()],
+        opts in options(),
+        width in 1usize..100,
+    ) {
+        let opts = Options { width, ..opts };
+        // Panics in the pipeline surface as `Error::Internal`
+        prop_assert!(try_reflow(&input, &opts).is_ok());
+        let once = format(&input, &opts);
+        prop_assert!(same_content(&input, &once, &opts), "output:\n{}", once);
+        let twice = format(&once, &opts);
+        prop_assert_eq!(once, twice);
+    }
+}